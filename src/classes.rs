@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use tree_sitter as TS;
+
+/// A C++ class or struct with a body, or a Python class.
+#[derive(Clone)]
+pub struct Class {
+    pub path: String,
+    /// The name including the enclosing namespaces and classes, e.g. `shapes::Rect`.
+    pub qualified_name: String,
+    pub line: usize,
+    pub methods: usize,
+    /// The names of the base classes as written but without template arguments.
+    pub bases: Vec<String>,
+}
+
+impl Class {
+    /// Create a class from a C++ class or struct specifier or a Python class definition,
+    /// `methods` are the methods found in the whole file.
+    pub fn new(path: &str, node: &TS::Node, content: &str, methods: &[TS::Node]) -> Self {
+        let name = node
+            .child_by_field_name("name")
+            .map_or("<anonymous>", |n| &content[n.byte_range()]);
+        let mut scopes = vec![without_arguments(name)];
+        let mut parent = node.parent();
+        while let Some(p) = parent {
+            if matches!(
+                p.kind(),
+                "namespace_definition"
+                    | "class_specifier"
+                    | "struct_specifier"
+                    | "class_definition"
+            ) {
+                if let Some(n) = p.child_by_field_name("name") {
+                    scopes.push(without_arguments(&content[n.byte_range()]));
+                }
+            }
+            parent = p.parent();
+        }
+        scopes.reverse();
+        let mut bases = vec![];
+        let mut cursor = node.walk();
+        for clause in node
+            .children(&mut cursor)
+            .filter(|c| matches!(c.kind(), "base_class_clause" | "argument_list"))
+        {
+            let mut cursor = clause.walk();
+            for base in clause.named_children(&mut cursor) {
+                if matches!(
+                    base.kind(),
+                    "type_identifier"
+                        | "qualified_identifier"
+                        | "template_type"
+                        | "identifier"
+                        | "attribute"
+                        | "subscript"
+                ) {
+                    bases.push(without_arguments(&content[base.byte_range()]));
+                }
+            }
+        }
+        Self {
+            path: path.to_string(),
+            qualified_name: scopes.join("::"),
+            line: node.start_position().row + 1,
+            methods: methods
+                .iter()
+                .filter(|m| m.parent().and_then(|p| p.parent()) == Some(*node))
+                .count(),
+            bases,
+        }
+    }
+}
+
+/// Remove template arguments and Python subscripts, e.g. `Base<int>` or `Generic[T]`.
+fn without_arguments(name: &str) -> String {
+    let name = name.split(['<', '[']).next().unwrap_or(name);
+    name.split_whitespace().collect()
+}
+
+/// Get the depth of inheritance of each class, only counting base classes that are found among
+/// `classes`. A class without any known base class has depth 0. Base classes are looked up like
+/// C++ names, from the namespace of the class outwards.
+pub fn inheritance_depths(classes: &[&Class]) -> Vec<usize> {
+    let classes_by_name: HashMap<&str, &Class> = classes
+        .iter()
+        .map(|c| (c.qualified_name.as_str(), *c))
+        .collect();
+    let mut depths = HashMap::new();
+    classes
+        .iter()
+        .map(|c| depth(c, &classes_by_name, &mut depths))
+        .collect()
+}
+
+fn depth<'a>(
+    class: &'a Class,
+    classes: &HashMap<&'a str, &'a Class>,
+    depths: &mut HashMap<&'a str, usize>,
+) -> usize {
+    let name = class.qualified_name.as_str();
+    if let Some(d) = depths.get(name) {
+        return *d;
+    }
+    // Guard against cycles, e.g. from classes that are defined more than once.
+    depths.insert(name, 0);
+    let d = class
+        .bases
+        .iter()
+        .filter_map(|b| resolve(&class.qualified_name, b, classes))
+        .map(|b| depth(b, classes, depths) + 1)
+        .max()
+        .unwrap_or(0);
+    depths.insert(name, d);
+    d
+}
+
+/// Find the class a base class name refers to, starting in the scope of `qualified_name`.
+fn resolve<'a>(
+    qualified_name: &str,
+    base: &str,
+    classes: &HashMap<&'a str, &'a Class>,
+) -> Option<&'a Class> {
+    let base = base.replace('.', "::");
+    if let Some(global) = base.strip_prefix("::") {
+        return classes.get(global).copied();
+    }
+    let scopes: Vec<&str> = qualified_name.split("::").collect();
+    (0..scopes.len()).rev().find_map(|n| {
+        let mut name = scopes[..n].join("::");
+        if !name.is_empty() {
+            name.push_str("::");
+        }
+        name.push_str(&base);
+        classes.get(name.as_str()).copied()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn class(name: &str, bases: &[&str]) -> Class {
+        Class {
+            path: "a.cpp".to_string(),
+            qualified_name: name.to_string(),
+            line: 1,
+            methods: 0,
+            bases: bases.iter().map(|b| b.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn depths() {
+        let classes = [
+            class("C", &["B", "std::string"]),
+            class("A", &[]),
+            class("B", &["A"]),
+            class("X", &["Y"]),
+            class("Y", &["X"]),
+        ];
+        let classes: Vec<&Class> = classes.iter().collect();
+        assert_eq!(inheritance_depths(&classes), vec![2, 0, 1, 2, 1]);
+        assert_eq!(without_arguments("ns::Base< int >"), "ns::Base");
+        assert_eq!(without_arguments("typing.Generic[T]"), "typing.Generic");
+    }
+
+    #[test]
+    fn same_name_in_namespaces() {
+        let classes = [
+            class("a::Base", &[]),
+            class("b::Base", &["a::Base"]),
+            class("b::Derived", &["Base"]),
+            class("a::Derived", &["Base"]),
+            class("c::Other", &["::b::Derived"]),
+        ];
+        let classes: Vec<&Class> = classes.iter().collect();
+        assert_eq!(inheritance_depths(&classes), vec![0, 1, 2, 1, 3]);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::{HashMap, HashSet};
+use tree_sitter as TS;
+
+/// Calculate the cyclomatic complexity of each function as one plus the number of decision
+/// points inside it. Decision points inside nested functions only count towards the innermost
+/// function.
+pub fn cyclomatic(functions: &[TS::Node], decisions: &[TS::Node]) -> Vec<usize> {
+    let ids: HashSet<usize> = functions.iter().map(|f| f.id()).collect();
+    let decisions: HashSet<usize> = decisions.iter().map(|d| d.id()).collect();
+    functions
+        .iter()
+        .map(|f| {
+            let mut complexity = 1;
+            let mut cursor = f.walk();
+            let mut depth = 0;
+            // Walk the function with a cursor since `Node::parent` and recursion are too slow
+            // or overflow the stack on deeply nested expressions.
+            loop {
+                let node = cursor.node();
+                if decisions.contains(&node.id()) {
+                    complexity += 1;
+                }
+                if (depth == 0 || !ids.contains(&node.id())) && cursor.goto_first_child() {
+                    depth += 1;
+                    continue;
+                }
+                while depth > 0 && !cursor.goto_next_sibling() {
+                    cursor.goto_parent();
+                    depth -= 1;
+                }
+                if depth == 0 {
+                    break;
+                }
+            }
+            complexity
+        })
+        .collect()
+}
+
+/// The role a node plays when calculating cognitive complexity.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Structure {
+    /// Breaks the linear flow and increases nesting, e.g. `if` or loops.
+    Branch,
+    /// Only increases nesting, e.g. closures.
+    Nest,
+    /// Breaks the linear flow without increasing nesting, e.g. `else` or `goto`.
+    Flow,
+    /// A binary logical operator, sequences of the same operator only count once.
+    Logical,
+    /// Only increases the block nesting depth, e.g. `{}` blocks and loop bodies.
+    Block,
+}
+
+impl Structure {
+    /// Get the structure from the name of the capture in the `Nesting` query.
+    pub fn from_capture(name: &str) -> Option<Self> {
+        match name {
+            "branch" => Some(Structure::Branch),
+            "nest" => Some(Structure::Nest),
+            "flow" => Some(Structure::Flow),
+            "logical" => Some(Structure::Logical),
+            "block" => Some(Structure::Block),
+            _ => None,
+        }
+    }
+}
+
+/// Calculate the cognitive complexity, following the SonarSource rules, and the maximum block
+/// nesting depth of each function, where the body of the function itself is depth 0. Nested
+/// functions are skipped as they are scored on their own.
+pub fn cognitive(
+    functions: &[TS::Node],
+    structures: &HashMap<usize, Structure>,
+) -> Vec<(usize, usize)> {
+    let ids: HashSet<usize> = functions.iter().map(|f| f.id()).collect();
+    functions
+        .iter()
+        .map(|f| {
+            let mut score = (0, 0);
+            walk(f, structures, &ids, &mut score);
+            score
+        })
+        .collect()
+}
+
+/// Walk a function with a cursor, since recursion overflows the stack on deeply nested code.
+fn walk(
+    function: &TS::Node,
+    structures: &HashMap<usize, Structure>,
+    functions: &HashSet<usize>,
+    score: &mut (usize, usize),
+) {
+    let mut cursor = function.walk();
+    // The nodes above the cursor with the nesting and block depth of their children.
+    let mut parents = vec![(*function, 0, 0)];
+    if !cursor.goto_first_child() {
+        return;
+    }
+    loop {
+        let child = cursor.node();
+        let (node, nesting, depth) = *parents.last().unwrap();
+        if !functions.contains(&child.id()) {
+            let mut child_nesting = nesting;
+            let mut child_depth = depth;
+            match structures.get(&child.id()) {
+                // An `else if` is already counted by its else clause.
+                Some(Structure::Branch)
+                    if node.kind() == "else_clause" && child.kind().starts_with("if_") => {}
+                Some(Structure::Branch) => {
+                    score.0 += 1 + nesting;
+                    child_nesting += 1;
+                }
+                Some(Structure::Nest) => child_nesting += 1,
+                Some(Structure::Flow) => score.0 += 1,
+                Some(Structure::Logical) => {
+                    let operator =
+                        |n: &TS::Node| n.child_by_field_name("operator").map(|o| o.kind());
+                    if structures.get(&node.id()) != Some(&Structure::Logical)
+                        || operator(&node) != operator(&child)
+                    {
+                        score.0 += 1;
+                    }
+                }
+                // The body of the function itself doesn't count.
+                Some(Structure::Block) if functions.contains(&node.id()) => {}
+                Some(Structure::Block) => child_depth += 1,
+                None => {}
+            }
+            score.1 = score.1.max(child_depth);
+            if cursor.goto_first_child() {
+                parents.push((child, child_nesting, child_depth));
+                continue;
+            }
+        }
+        while !cursor.goto_next_sibling() {
+            cursor.goto_parent();
+            parents.pop();
+            if parents.is_empty() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,506 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use tree_sitter as TS;
+
+/// How an import refers to another file, decides how it is resolved.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImportKind {
+    /// `#include "file"`, relative to the including file.
+    Include,
+    /// `#include <file>`.
+    SystemInclude,
+    /// Rust `mod name;`.
+    Module,
+    /// Rust `use path;`.
+    Use,
+    /// Rust `extern crate name;`, always external.
+    Crate,
+    /// Zig `@import("file")`.
+    File,
+}
+
+impl ImportKind {
+    /// Get the kind of import from the name of a capture in a `Dependencies` query.
+    pub fn from_capture(name: &str) -> Option<Self> {
+        match name {
+            "include" => Some(ImportKind::Include),
+            "system_include" => Some(ImportKind::SystemInclude),
+            "module" => Some(ImportKind::Module),
+            "use" => Some(ImportKind::Use),
+            "crate" => Some(ImportKind::Crate),
+            "import" => Some(ImportKind::File),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Import {
+    pub kind: ImportKind,
+    pub name: String,
+    /// The number of inline modules the import is in, `super` in those refers to the same file.
+    pub inline_modules: usize,
+}
+
+impl Import {
+    /// Create an import from a captured node.
+    pub fn from_node(kind: ImportKind, node: &TS::Node, content: &str) -> Self {
+        let mut import = Self::new(kind, &content[node.byte_range()]);
+        let mut parent = node.parent();
+        while let Some(p) = parent {
+            if p.kind() == "mod_item" {
+                import.inline_modules += 1;
+            }
+            parent = p.parent();
+        }
+        import
+    }
+
+    /// Create an import from the text of the captured node, stripping quotes and for `use` the
+    /// imported names so only the module path is left.
+    pub fn new(kind: ImportKind, text: &str) -> Self {
+        let name = match kind {
+            ImportKind::Include => text.trim_matches('"'),
+            ImportKind::SystemInclude => text.trim_start_matches('<').trim_end_matches('>'),
+            ImportKind::Use => {
+                let path = text.split('{').next().unwrap_or(text);
+                let path = path.split(" as ").next().unwrap_or(path);
+                path.trim().trim_end_matches('*').trim_end_matches("::")
+            }
+            _ => text,
+        };
+        Self {
+            kind,
+            name: name.to_string(),
+            inline_modules: 0,
+        }
+    }
+}
+
+/// A parsed file with its imports and the metrics shown in the dependency graph.
+#[derive(Clone)]
+pub struct FileDeps {
+    pub path: String,
+    pub language: String,
+    pub lines: usize,
+    pub functions: usize,
+    pub imports: Vec<Import>,
+}
+
+/// The dependencies between the scanned files, files are sorted by path.
+pub struct Graph<'a> {
+    pub files: Vec<&'a FileDeps>,
+    pub edges: Vec<(usize, usize)>,
+    /// Imports of files that aren't among the scanned ones, e.g. the standard library.
+    pub unresolved: usize,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(mut files: Vec<&'a FileDeps>) -> Self {
+        files.sort_by_cached_key(|f| normalize(&f.path));
+        let index: HashMap<String, usize> = files
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (normalize(&f.path), i))
+            .collect();
+        let resolver = Resolver::new(index.keys().cloned().collect());
+
+        let mut edges = HashSet::new();
+        let mut unresolved = 0;
+        for (from, f) in files.iter().enumerate() {
+            for import in &f.imports {
+                match resolver.resolve(&normalize(&f.path), import) {
+                    Some(to) if index[&to] != from => {
+                        edges.insert((from, index[&to]));
+                    }
+                    Some(_) => {}
+                    None => unresolved += 1,
+                }
+            }
+        }
+        let mut edges: Vec<(usize, usize)> = edges.into_iter().collect();
+        edges.sort();
+        Self {
+            files,
+            edges,
+            unresolved,
+        }
+    }
+
+    pub fn fan_in(&self) -> Vec<usize> {
+        let mut fan_in = vec![0; self.files.len()];
+        for &(_, to) in &self.edges {
+            fan_in[to] += 1;
+        }
+        fan_in
+    }
+
+    pub fn fan_out(&self) -> Vec<usize> {
+        let mut fan_out = vec![0; self.files.len()];
+        for &(from, _) in &self.edges {
+            fan_out[from] += 1;
+        }
+        fan_out
+    }
+
+    /// Find the groups of files that depend on each other, i.e. the strongly connected
+    /// components with more than one file.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut tarjan = Tarjan {
+            adjacent: vec![vec![]; self.files.len()],
+            index: vec![None; self.files.len()],
+            low: vec![0; self.files.len()],
+            stack: vec![],
+            on_stack: vec![false; self.files.len()],
+            next: 0,
+            components: vec![],
+        };
+        for &(from, to) in &self.edges {
+            tarjan.adjacent[from].push(to);
+        }
+        for v in 0..self.files.len() {
+            if tarjan.index[v].is_none() {
+                tarjan.visit(v);
+            }
+        }
+        let mut cycles: Vec<Vec<usize>> = tarjan
+            .components
+            .into_iter()
+            .filter(|c| c.len() > 1)
+            .map(|mut c| {
+                c.sort();
+                c
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for f in &self.files {
+            let path = dot_escape(&normalize(&f.path));
+            dot += &format!(
+                "    \"{}\" [label=\"{}\\nlines: {}, functions: {}\"];\n",
+                path, path, f.lines, f.functions
+            );
+        }
+        for &(from, to) in &self.edges {
+            dot += &format!(
+                "    \"{}\" -> \"{}\";\n",
+                dot_escape(&normalize(&self.files[from].path)),
+                dot_escape(&normalize(&self.files[to].path))
+            );
+        }
+        dot + "}\n"
+    }
+
+    pub fn to_json(&self) -> String {
+        let (fan_in, fan_out) = (self.fan_in(), self.fan_out());
+        let path = |i: usize| json_string(&normalize(&self.files[i].path));
+        let nodes: Vec<String> = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                format!(
+                    "{{\"path\": {}, \"language\": {}, \"lines\": {}, \"functions\": {}, \"fan_in\": {}, \"fan_out\": {}}}",
+                    path(i),
+                    json_string(&f.language),
+                    f.lines,
+                    f.functions,
+                    fan_in[i],
+                    fan_out[i]
+                )
+            })
+            .collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|&(from, to)| format!("{{\"from\": {}, \"to\": {}}}", path(from), path(to)))
+            .collect();
+        let cycles: Vec<String> = self
+            .cycles()
+            .iter()
+            .map(|c| {
+                let files: Vec<String> = c.iter().map(|&i| path(i)).collect();
+                format!("[{}]", files.join(", "))
+            })
+            .collect();
+        format!(
+            "{{\n  \"nodes\": {},\n  \"edges\": {},\n  \"cycles\": {}\n}}\n",
+            json_list(&nodes),
+            json_list(&edges),
+            json_list(&cycles)
+        )
+    }
+}
+
+struct Tarjan {
+    adjacent: Vec<Vec<usize>>,
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan {
+    fn visit(&mut self, v: usize) {
+        self.index[v] = Some(self.next);
+        self.low[v] = self.next;
+        self.next += 1;
+        self.stack.push(v);
+        self.on_stack[v] = true;
+        for i in 0..self.adjacent[v].len() {
+            let w = self.adjacent[v][i];
+            match self.index[w] {
+                None => {
+                    self.visit(w);
+                    self.low[v] = self.low[v].min(self.low[w]);
+                }
+                Some(index) if self.on_stack[w] => self.low[v] = self.low[v].min(index),
+                Some(_) => {}
+            }
+        }
+        if Some(self.low[v]) == self.index[v] {
+            let mut component = vec![];
+            while let Some(w) = self.stack.pop() {
+                self.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// Finds the scanned file an import refers to. Include paths and crate dependencies aren't
+/// known so includes that aren't relative to the including file are matched by their suffix and
+/// imports of other crates are left unresolved.
+struct Resolver {
+    files: HashSet<String>,
+    by_name: HashMap<String, Vec<String>>,
+}
+
+impl Resolver {
+    fn new(files: HashSet<String>) -> Self {
+        let mut by_name: HashMap<String, Vec<String>> = HashMap::new();
+        for f in &files {
+            let name = f.rsplit('/').next().unwrap_or(f);
+            by_name.entry(name.to_string()).or_default().push(f.clone());
+        }
+        for paths in by_name.values_mut() {
+            paths.sort();
+        }
+        Self { files, by_name }
+    }
+
+    fn resolve(&self, from: &str, import: &Import) -> Option<String> {
+        let dir = Path::new(from).parent().unwrap_or(Path::new(""));
+        match import.kind {
+            ImportKind::Include => self
+                .find(&dir.join(&import.name))
+                .or_else(|| self.find_suffix(&import.name)),
+            ImportKind::SystemInclude => self.find_suffix(&import.name),
+            ImportKind::Module => self.module_file(&module_dir(from).join(&import.name)),
+            ImportKind::Use => self.resolve_use(from, &import.name, import.inline_modules),
+            ImportKind::Crate => None,
+            ImportKind::File => self.find(&dir.join(&import.name)),
+        }
+    }
+
+    fn find(&self, path: &Path) -> Option<String> {
+        let path = normalize(path.to_str()?);
+        self.files.contains(&path).then_some(path)
+    }
+
+    fn find_suffix(&self, name: &str) -> Option<String> {
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        self.by_name
+            .get(file_name)?
+            .iter()
+            .find(|f| *f == name || f.ends_with(&format!("/{}", name)))
+            .cloned()
+    }
+
+    /// Get the file of the module whose submodules are in `dir`.
+    fn module_file(&self, dir: &Path) -> Option<String> {
+        self.find(&dir.with_extension("rs"))
+            .or_else(|| self.find(&dir.join("mod.rs")))
+            .or_else(|| self.find(&dir.join("lib.rs")))
+            .or_else(|| self.find(&dir.join("main.rs")))
+    }
+
+    /// Resolve a `use` path to the file of the longest module in the path, e.g. `crate::a::B`
+    /// gives `a.rs` in the directory of the crate root. Paths relative to one of the
+    /// `inline_modules` the import is in resolve to the file itself.
+    fn resolve_use(&self, from: &str, path: &str, inline_modules: usize) -> Option<String> {
+        let mut segments: Vec<&str> = path.split("::").filter(|s| !s.is_empty()).collect();
+        let base = match segments.first() {
+            Some(&"crate") => {
+                segments.remove(0);
+                Path::new(from)
+                    .ancestors()
+                    .skip(1)
+                    .find(|d| {
+                        self.find(&d.join("lib.rs")).is_some()
+                            || self.find(&d.join("main.rs")).is_some()
+                    })?
+                    .to_path_buf()
+            }
+            Some(&"self") if inline_modules > 0 => return Some(from.to_string()),
+            Some(&"self") => {
+                segments.remove(0);
+                module_dir(from)
+            }
+            Some(&"super") => {
+                let mut dir = module_dir(from);
+                let mut inline_modules = inline_modules;
+                while segments.first() == Some(&"super") {
+                    segments.remove(0);
+                    if inline_modules > 0 {
+                        inline_modules -= 1;
+                    } else {
+                        dir = dir.parent()?.to_path_buf();
+                    }
+                }
+                if inline_modules > 0 {
+                    return Some(from.to_string());
+                }
+                dir
+            }
+            // Modules in the current module can be used without a prefix.
+            _ => module_dir(from),
+        };
+        if segments.is_empty() {
+            return self.module_file(&base);
+        }
+        (1..=segments.len())
+            .rev()
+            .find_map(|n| self.module_file(&base.join(segments[..n].join("/"))))
+    }
+}
+
+/// Get the directory where the submodules of a Rust file are.
+fn module_dir(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    match path.file_stem().and_then(|s| s.to_str()) {
+        Some("mod" | "lib" | "main") | None => dir.to_path_buf(),
+        Some(stem) => dir.join(stem),
+    }
+}
+
+/// Remove `.` and `..` from a path so the same file always gets the same name.
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = vec![];
+    for c in Path::new(path).components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir if parts.last().is_some_and(|p| *p != "..") => {
+                parts.pop();
+            }
+            Component::RootDir => parts.push(""),
+            _ => parts.push(c.as_os_str().to_str().unwrap_or("")),
+        }
+    }
+    parts.join("/")
+}
+
+fn json_list(items: &[String]) -> String {
+    if items.is_empty() {
+        return "[]".to_string();
+    }
+    format!("[\n    {}\n  ]", items.join(",\n    "))
+}
+
+/// Escape a string to be put between quotes in DOT, where only `"` and `\` need escaping.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json + "\""
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn file(path: &str, imports: &[(ImportKind, &str)]) -> FileDeps {
+        FileDeps {
+            path: path.to_string(),
+            language: "Rust".to_string(),
+            lines: 1,
+            functions: 0,
+            imports: imports
+                .iter()
+                .map(|(kind, text)| Import::new(*kind, text))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn resolve_rust() {
+        let files = [
+            file(
+                "./src/main.rs",
+                &[(ImportKind::Module, "a"), (ImportKind::Use, "std::fmt")],
+            ),
+            file("src/a.rs", &[(ImportKind::Module, "b")]),
+            file(
+                "src/a/b.rs",
+                &[
+                    (ImportKind::Use, "crate::a::{self, X}"),
+                    (ImportKind::Use, "super::*"),
+                ],
+            ),
+        ];
+        let graph = Graph::new(files.iter().collect());
+        // src/a.rs, src/a/b.rs, src/main.rs
+        assert_eq!(graph.edges, vec![(0, 1), (1, 0), (2, 0)]);
+        assert_eq!(graph.unresolved, 1);
+        assert_eq!(graph.cycles(), vec![vec![0, 1]]);
+        assert_eq!(graph.fan_in(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn resolve_includes() {
+        let files = [
+            file(
+                "lib/a.c",
+                &[
+                    (ImportKind::Include, "\"a.h\""),
+                    (ImportKind::SystemInclude, "<inc/b.h>"),
+                    (ImportKind::SystemInclude, "<stdio.h>"),
+                ],
+            ),
+            file("lib/a.h", &[]),
+            file("lib/inc/b.h", &[(ImportKind::Include, "\"../a.h\"")]),
+        ];
+        let graph = Graph::new(files.iter().collect());
+        assert_eq!(graph.edges, vec![(0, 1), (0, 2), (2, 1)]);
+        assert_eq!(graph.unresolved, 1);
+        assert!(graph.cycles().is_empty());
+    }
+
+    #[test]
+    fn dot_quoting() {
+        let files = [file("say \"hi\"\\a.c", &[])];
+        let graph = Graph::new(files.iter().collect());
+        assert!(graph.to_dot().contains("\"say \\\"hi\\\"\\\\a.c\""));
+    }
+}
@@ -0,0 +1,98 @@
+/// Upper bounds (inclusive) of the buckets used for function length histograms, anything above
+/// the last bound ends up in an extra bucket.
+pub const LENGTH_BUCKETS: [usize; 4] = [10, 25, 50, 100];
+
+/// A set of samples, e.g. the lengths of all functions in a language.
+pub struct Distribution {
+    values: Vec<usize>,
+}
+
+impl Distribution {
+    pub fn new(values: impl Iterator<Item = usize>) -> Self {
+        let mut values: Vec<usize> = values.collect();
+        values.sort();
+        Self { values }
+    }
+
+    pub fn total(&self) -> usize {
+        self.values.iter().sum()
+    }
+
+    pub fn min(&self) -> usize {
+        self.values.first().copied().unwrap_or(0)
+    }
+
+    pub fn max(&self) -> usize {
+        self.values.last().copied().unwrap_or(0)
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        self.total() as f64 / self.values.len() as f64
+    }
+
+    pub fn median(&self) -> usize {
+        self.percentile(50)
+    }
+
+    /// Get the `p`th percentile using the nearest rank method.
+    pub fn percentile(&self, p: usize) -> usize {
+        if self.values.is_empty() {
+            return 0;
+        }
+        let rank = (p * self.values.len()).div_ceil(100).max(1);
+        self.values[rank - 1]
+    }
+
+    /// Count the samples in each bucket, `bounds` are the inclusive upper bounds of all but the
+    /// last bucket.
+    pub fn histogram(&self, bounds: &[usize]) -> Vec<usize> {
+        let mut buckets = vec![0; bounds.len() + 1];
+        for v in &self.values {
+            let i = bounds.iter().position(|b| v <= b).unwrap_or(bounds.len());
+            buckets[i] += 1;
+        }
+        buckets
+    }
+}
+
+/// Get the names of the buckets in a histogram, e.g. `1-10` and `>100`.
+pub fn bucket_names(bounds: &[usize]) -> Vec<String> {
+    let mut names = vec![];
+    let mut low = 1;
+    for b in bounds {
+        names.push(format!("{}-{}", low, b));
+        low = b + 1;
+    }
+    names.push(format!(">{}", low - 1));
+    names
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn percentiles() {
+        let d = Distribution::new((1..=100).rev());
+        assert_eq!(d.min(), 1);
+        assert_eq!(d.max(), 100);
+        assert_eq!(d.mean(), 50.5);
+        assert_eq!(d.median(), 50);
+        assert_eq!(d.percentile(90), 90);
+        assert_eq!(d.percentile(99), 99);
+        assert_eq!(Distribution::new([7].into_iter()).percentile(99), 7);
+    }
+
+    #[test]
+    fn histogram() {
+        let d = Distribution::new([1, 10, 11, 26, 50, 100, 101, 500].into_iter());
+        assert_eq!(d.histogram(&LENGTH_BUCKETS), vec![2, 1, 2, 1, 2]);
+        assert_eq!(
+            bucket_names(&LENGTH_BUCKETS),
+            vec!["1-10", "11-25", "26-50", "51-100", ">100"]
+        );
+    }
+}
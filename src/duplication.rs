@@ -0,0 +1,234 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use tree_sitter as TS;
+
+const BASE: u64 = 1_000_003;
+
+#[derive(Clone)]
+pub struct Token {
+    hash: u64,
+    line: usize,
+}
+
+/// The tokens of a file, used to find duplicated code.
+#[derive(Clone)]
+pub struct FileTokens {
+    pub path: String,
+    pub tokens: Vec<Token>,
+}
+
+/// A place where a duplicated block of code is found, lines start at 1.
+pub struct Location {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The lines in the block that contain code, i.e. not blank or comment lines.
+    pub lines: Vec<usize>,
+}
+
+/// A block of code that is found in two places.
+pub struct Duplicate {
+    pub tokens: usize,
+    pub first: Location,
+    pub second: Location,
+}
+
+/// Get the tokens from the leaves below `node`, skipping comments. With `normalize` all
+/// identifiers are treated as the same token so code that only differs in naming is found.
+pub fn tokenize(node: &TS::Node, content: &str, normalize: bool) -> Vec<Token> {
+    let mut tokens = vec![];
+    collect_tokens(node, content, normalize, &mut tokens);
+    tokens
+}
+
+/// Walk the tree with a cursor, since recursion overflows the stack on deeply nested code.
+fn collect_tokens(node: &TS::Node, content: &str, normalize: bool, tokens: &mut Vec<Token>) {
+    let mut cursor = node.walk();
+    let mut depth = 0;
+    loop {
+        if !collect_token(&cursor.node(), content, normalize, tokens) && cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+        while depth > 0 && !cursor.goto_next_sibling() {
+            cursor.goto_parent();
+            depth -= 1;
+        }
+        if depth == 0 {
+            return;
+        }
+    }
+}
+
+/// Add a node as a token, returns false for nodes made up of other tokens.
+fn collect_token(node: &TS::Node, content: &str, normalize: bool, tokens: &mut Vec<Token>) -> bool {
+    if node.is_extra() || node.is_missing() {
+        return true;
+    }
+    let literal =
+        node.is_named() && (node.kind().contains("string") || node.kind().ends_with("literal"));
+    if !literal && node.child_count() > 0 {
+        return false;
+    }
+    let mut hasher = DefaultHasher::new();
+    if normalize && node.kind().contains("identifier") {
+        node.kind().hash(&mut hasher);
+    } else {
+        content[node.byte_range()].hash(&mut hasher);
+    }
+    tokens.push(Token {
+        hash: hasher.finish(),
+        line: node.start_position().row + 1,
+    });
+    true
+}
+
+/// Find blocks of at least `min_tokens` tokens that appear more than once. Each block is
+/// extended as far as possible and reported against the first place it was seen.
+pub fn find_duplicates(files: &[FileTokens], min_tokens: usize) -> Vec<Duplicate> {
+    let mut duplicates = vec![];
+    if min_tokens == 0 {
+        return duplicates;
+    }
+    let prefixes: Vec<Vec<u64>> = files.iter().map(|f| prefix_hashes(&f.tokens)).collect();
+    let power = (0..min_tokens).fold(1u64, |p, _| p.wrapping_mul(BASE));
+    let window = |f: usize, i: usize| {
+        prefixes[f][i + min_tokens].wrapping_sub(prefixes[f][i].wrapping_mul(power))
+    };
+    let same = |a: (usize, usize), b: (usize, usize)| {
+        files[a.0].tokens[a.1].hash == files[b.0].tokens[b.1].hash
+    };
+
+    // Go through the files in a fixed order so the result doesn't depend on how the files
+    // were split between threads.
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by_key(|&f| &files[f].path);
+
+    let mut seen: HashMap<u64, (usize, usize)> = HashMap::new();
+    for f in order {
+        let file = &files[f];
+        let mut i = 0;
+        while i + min_tokens <= file.tokens.len() {
+            let hash = window(f, i);
+            if let Some(&(of, oi)) = seen.get(&hash) {
+                let overlaps = |n: usize| of == f && oi + n > i;
+                if !overlaps(min_tokens) && (0..min_tokens).all(|n| same((of, oi + n), (f, i + n)))
+                {
+                    let mut n = min_tokens;
+                    while i + n < file.tokens.len()
+                        && oi + n < files[of].tokens.len()
+                        && !overlaps(n + 1)
+                        && same((of, oi + n), (f, i + n))
+                    {
+                        n += 1;
+                    }
+                    duplicates.push(Duplicate {
+                        tokens: n,
+                        first: location(&files[of], oi, n),
+                        second: location(file, i, n),
+                    });
+                    i += n;
+                    continue;
+                }
+            }
+            seen.entry(hash).or_insert((f, i));
+            i += 1;
+        }
+    }
+    duplicates
+}
+
+/// Find duplicates in every `step`th language starting at `first`, used to split the languages
+/// between the threads.
+pub fn find_in_share(
+    languages: &[(String, Vec<FileTokens>)],
+    first: usize,
+    step: usize,
+    min_tokens: usize,
+) -> Vec<(String, Vec<Duplicate>)> {
+    languages
+        .iter()
+        .skip(first)
+        .step_by(step)
+        .map(|(name, files)| (name.clone(), find_duplicates(files, min_tokens)))
+        .collect()
+}
+
+fn prefix_hashes(tokens: &[Token]) -> Vec<u64> {
+    let mut prefixes = vec![0u64];
+    for t in tokens {
+        let last = *prefixes.last().unwrap();
+        prefixes.push(last.wrapping_mul(BASE).wrapping_add(t.hash));
+    }
+    prefixes
+}
+
+fn location(file: &FileTokens, start: usize, len: usize) -> Location {
+    let mut lines: Vec<usize> = file.tokens[start..start + len]
+        .iter()
+        .map(|t| t.line)
+        .collect();
+    lines.dedup();
+    Location {
+        path: file.path.clone(),
+        start_line: file.tokens[start].line,
+        end_line: file.tokens[start + len - 1].line,
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tokens(path: &str, content: &str, normalize: bool) -> FileTokens {
+        let mut parser = TS::Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(content, None).unwrap();
+        FileTokens {
+            path: path.to_string(),
+            tokens: tokenize(&tree.root_node(), content, normalize),
+        }
+    }
+
+    #[test]
+    fn finds_duplicates_across_files() {
+        let a = tokens("a.rs", "fn a() {\n    let x = 1 + 2; // one\n}\n", false);
+        let b = tokens("b.rs", "fn b() {\n\n    let x = 1 + 2;\n}\n", false);
+        let duplicates = find_duplicates(&[a, b], 8);
+        assert_eq!(duplicates.len(), 1);
+        // `() { let x = 1 + 2; }`
+        assert_eq!(duplicates[0].tokens, 11);
+        assert_eq!(duplicates[0].first.path, "a.rs");
+        assert_eq!(duplicates[0].first.start_line, 1);
+        assert_eq!(duplicates[0].first.end_line, 3);
+        assert_eq!(duplicates[0].second.path, "b.rs");
+        assert_eq!(duplicates[0].second.end_line, 4);
+        assert_eq!(duplicates[0].second.lines, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn normalize_identifiers() {
+        let a = tokens("a.rs", "fn a() { let x = y + 2; }", true);
+        let b = tokens("b.rs", "fn b() { let z = w + 2; }", true);
+        assert_eq!(find_duplicates(&[a, b], 12).len(), 1);
+        let a = tokens("a.rs", "fn a() { let x = y + 2; }", false);
+        let b = tokens("b.rs", "fn b() { let z = w + 2; }", false);
+        assert_eq!(find_duplicates(&[a, b], 12).len(), 0);
+    }
+
+    #[test]
+    fn deeply_nested() {
+        let depth = 100_000;
+        let content = format!(
+            "fn f() -> i32 {{ {}1{} }}",
+            "(".repeat(depth),
+            ")".repeat(depth)
+        );
+        // fn f ( ) -> i32 { 1 } and the parentheses.
+        assert_eq!(tokens("a.rs", &content, false).tokens.len(), 2 * depth + 9);
+    }
+}
@@ -0,0 +1,94 @@
+/// Start of common binary formats: ELF, Mach-O, PE, Java class files, PNG, JPEG, GIF, PDF,
+/// zip, gzip and WebAssembly.
+const MAGIC_NUMBERS: [&[u8]; 13] = [
+    b"\x7fELF",
+    b"\xfe\xed\xfa\xce",
+    b"\xfe\xed\xfa\xcf",
+    b"\xcf\xfa\xed\xfe",
+    b"MZ\x90\x00",
+    b"\xca\xfe\xba\xbe",
+    b"\x89PNG",
+    b"\xff\xd8\xff",
+    b"GIF8",
+    b"%PDF-",
+    b"PK\x03\x04",
+    b"\x1f\x8b",
+    b"\0asm",
+];
+
+/// How much of the start of a file is searched for NUL bytes.
+const SNIFF_LEN: usize = 8000;
+
+/// The characters of Windows-1252 for the bytes 0x80-0x9f, the rest are the same as Latin-1.
+/// Bytes that are undefined in Windows-1252 keep their Latin-1 meaning.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// Decode the content of a file as text, returns `None` for binary files. UTF-16 needs a byte
+/// order mark, other files are read as UTF-8 if they are valid UTF-8 and otherwise as
+/// Windows-1252. Any byte order mark is removed.
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    if let Some(rest) = bytes.strip_prefix(b"\xef\xbb\xbf") {
+        return Some(String::from_utf8(rest.to_vec()).unwrap_or_else(|_| windows_1252(rest)));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xff\xfe") {
+        return Some(utf16(rest, u16::from_le_bytes));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xfe\xff") {
+        return Some(utf16(rest, u16::from_be_bytes));
+    }
+    if is_binary(bytes) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Some(s.to_string()),
+        Err(_) => Some(windows_1252(bytes)),
+    }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    MAGIC_NUMBERS.iter().any(|m| bytes.starts_with(m))
+        || bytes[..bytes.len().min(SNIFF_LEN)].contains(&0)
+}
+
+fn utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks_exact(2).map(|b| from_bytes([b[0], b[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9f => WINDOWS_1252[(b - 0x80) as usize],
+            _ => b as char,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_text() {
+        assert_eq!(decode(b"int a;\n").as_deref(), Some("int a;\n"));
+        assert_eq!(decode(b"\xef\xbb\xbfint a;").as_deref(), Some("int a;"));
+        assert_eq!(decode(b"/* caf\xe9 */").as_deref(), Some("/* café */"));
+        assert_eq!(decode(b"\x93quoted\x94").as_deref(), Some("“quoted”"));
+        assert_eq!(decode(b"\xff\xfea\0b\0").as_deref(), Some("ab"));
+        assert_eq!(decode(b"\xfe\xff\0a\0b").as_deref(), Some("ab"));
+    }
+
+    #[test]
+    fn detect_binary() {
+        assert_eq!(decode(b"\x7fELF\x02\x01\x01"), None);
+        assert_eq!(decode(b"GIF89a"), None);
+        assert_eq!(decode(b"text\0with nul"), None);
+        assert!(!is_binary(b"plain text"));
+    }
+}
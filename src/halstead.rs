@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use tree_sitter as TS;
+
+/// Halstead token counts, operands are identifiers and literals and operators are everything
+/// else like keywords and punctuation.
+#[derive(Clone, Default)]
+pub struct Halstead {
+    pub distinct_operators: usize,
+    pub distinct_operands: usize,
+    pub operators: usize,
+    pub operands: usize,
+}
+
+impl Halstead {
+    /// Count the operators and operands among the leaves below `node`. Comments are skipped
+    /// and string literals count as a single operand.
+    pub fn new(node: &TS::Node, content: &str) -> Self {
+        let mut operators = HashMap::new();
+        let mut operands = HashMap::new();
+        count_tokens(node, content, &mut operators, &mut operands);
+        Self {
+            distinct_operators: operators.len(),
+            distinct_operands: operands.len(),
+            operators: operators.values().sum(),
+            operands: operands.values().sum(),
+        }
+    }
+
+    pub fn vocabulary(&self) -> usize {
+        self.distinct_operators + self.distinct_operands
+    }
+
+    pub fn length(&self) -> usize {
+        self.operators + self.operands
+    }
+
+    pub fn volume(&self) -> f64 {
+        if self.vocabulary() == 0 {
+            return 0.0;
+        }
+        self.length() as f64 * (self.vocabulary() as f64).log2()
+    }
+
+    pub fn difficulty(&self) -> f64 {
+        if self.distinct_operands == 0 {
+            return 0.0;
+        }
+        self.distinct_operators as f64 / 2.0 * self.operands as f64 / self.distinct_operands as f64
+    }
+
+    pub fn effort(&self) -> f64 {
+        self.difficulty() * self.volume()
+    }
+}
+
+/// Walk the tree with a cursor, since recursion overflows the stack on deeply nested code.
+fn count_tokens<'a>(
+    node: &TS::Node,
+    content: &'a str,
+    operators: &mut HashMap<&'a str, usize>,
+    operands: &mut HashMap<&'a str, usize>,
+) {
+    let mut cursor = node.walk();
+    let mut depth = 0;
+    loop {
+        if !count_token(&cursor.node(), content, operators, operands) && cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+        while depth > 0 && !cursor.goto_next_sibling() {
+            cursor.goto_parent();
+            depth -= 1;
+        }
+        if depth == 0 {
+            return;
+        }
+    }
+}
+
+/// Count a node as an operator or operand, returns false for nodes made up of other tokens.
+fn count_token<'a>(
+    node: &TS::Node,
+    content: &'a str,
+    operators: &mut HashMap<&'a str, usize>,
+    operands: &mut HashMap<&'a str, usize>,
+) -> bool {
+    if node.is_extra() || node.is_missing() {
+        return true;
+    }
+    let text = &content[node.byte_range()];
+    if node.is_named() && (node.kind().contains("string") || node.kind().ends_with("literal")) {
+        *operands.entry(text).or_default() += 1;
+    } else if node.child_count() == 0 {
+        if node.is_named() {
+            *operands.entry(text).or_default() += 1;
+        } else if !matches!(node.kind(), ")" | "]" | "}") {
+            // Brackets are counted once as a pair.
+            *operators.entry(node.kind()).or_default() += 1;
+        }
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Calculate the maintainability index, normalized to 0-100, from the Halstead volume,
+/// cyclomatic complexity and number of lines.
+pub fn maintainability_index(volume: f64, complexity: usize, lines: usize) -> f64 {
+    let mi = 171.0
+        - 5.2 * volume.max(1.0).ln()
+        - 0.23 * complexity as f64
+        - 16.2 * (lines.max(1) as f64).ln();
+    (mi * 100.0 / 171.0).clamp(0.0, 100.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn count_rust_tokens() {
+        let content = "fn f(a: u32) -> u32 { a + 1 } // comment";
+        let mut parser = TS::Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(content, None).unwrap();
+        let h = Halstead::new(&tree.root_node(), content);
+        // Operators: fn ( : -> { +, operands: f a u32 u32 a 1
+        assert_eq!(h.distinct_operators, 6);
+        assert_eq!(h.distinct_operands, 4);
+        assert_eq!(h.operators, 6);
+        assert_eq!(h.operands, 6);
+        assert_eq!(h.vocabulary(), 10);
+        assert_eq!(h.length(), 12);
+        assert_eq!(h.difficulty(), 4.5);
+    }
+
+    #[test]
+    fn deeply_nested() {
+        let depth = 100_000;
+        let content = format!(
+            "fn f() -> i32 {{ {}1{} }}",
+            "(".repeat(depth),
+            ")".repeat(depth)
+        );
+        let mut parser = TS::Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(&content, None).unwrap();
+        let h = Halstead::new(&tree.root_node(), &content);
+        // Operators: fn ( -> { and the nested (, operands: f i32 1
+        assert_eq!(h.operators, depth + 4);
+        assert_eq!(h.operands, 3);
+    }
+}
@@ -1,8 +1,30 @@
-use lazy_static::lazy_static;
 use tree_sitter as TS;
 
 #[macro_export]
 macro_rules! lang_struct {
+    ($language_vec: expr, $name:ident, $(aliases ($($alias:expr),+),)? syntax $syntax:expr, $($file_ending:expr),*) => {
+        pub struct $name {}
+        impl Language for $name {
+            fn name(&self) -> &str {
+                stringify!($name)
+            }
+            $(
+            fn aliases(&self) -> &'static [&'static str] {
+                &[$($alias),+]
+            }
+            )?
+            fn matches_filename(&self, filename: &str) -> bool {
+            $(
+                if filename.ends_with($file_ending) {return true;}
+            )*
+            return false;
+            }
+            fn syntax(&self) -> Option<&'static Syntax> {
+                Some(&$syntax)
+            }
+        }
+        $language_vec.push(Box::new($name {}));
+    };
     ($language_vec: expr, $name:ident, $($file_ending:expr),*) => {
         pub struct $name {}
         impl Language for $name {
@@ -21,6 +43,7 @@ macro_rules! lang_struct {
 
     ($language_vec:expr, $name:ident,
         ending $file_ending:expr,
+        $(aliases ($($alias:expr),+),)?
         ts $ts:ident,
         $($qtype:expr; $query:expr),+
         ) => {
@@ -28,12 +51,14 @@ macro_rules! lang_struct {
                 $language_vec,
                 $name,
                 endings ($file_ending),
+                $(aliases ($($alias),+),)?
                 ts $ts,
                 $($qtype; $query),+
             );
     };
     ($language_vec:expr, $name:ident,
         endings ($($file_endings:expr),+),
+        $(aliases ($($alias:expr),+),)?
         ts $ts:ident,
         $($qtype:expr; $query:expr),+
         ) => {
@@ -42,6 +67,11 @@ macro_rules! lang_struct {
         fn name(&self) -> &str {
             stringify!($name)
         }
+        $(
+        fn aliases(&self) -> &'static [&'static str] {
+            &[$($alias),+]
+        }
+        )?
         fn matches_filename(&self, filename: &str) -> bool {
             $(
                 if filename.ends_with($file_endings) {return true;}
@@ -51,7 +81,7 @@ macro_rules! lang_struct {
         fn language(&self) -> Option<TS::Language> {
             Some($ts::LANGUAGE.into())
         }
-        fn queries(&self) -> &'static Vec<Operation> {
+    fn queries(&self) -> &'static Vec<Operation> {
             lazy_static!{
                 static ref operations: Vec<Operation> = vec![
                 $(
@@ -66,13 +96,86 @@ macro_rules! lang_struct {
     };
 }
 
-#[derive(Eq, Hash, PartialEq, Clone, Debug)]
+#[derive(Eq, Hash, PartialEq, Ord, PartialOrd, Clone, Debug)]
 pub enum QType {
     Loops,
     Functions,
     Variables,
     Templates,
     Defines,
+    Comments,
+    DocComments,
+    Decisions,
+    Nesting,
+    UnsafeBlocks,
+    UnsafeLines,
+    UnsafeFunctions,
+    UnsafeImpls,
+    UnsafeTraits,
+    ExternBlocks,
+    /// Dereferences in unsafe code, the types aren't known so these are assumed to be raw
+    /// pointers.
+    RawPointerDerefs,
+    TestFunctions,
+    Structs,
+    Enums,
+    Unions,
+    Traits,
+    InherentImpls,
+    TraitImpls,
+    TypeAliases,
+    Macros,
+    Modules,
+    Uses,
+    Classes,
+    Methods,
+    VirtualFunctions,
+    PureVirtualFunctions,
+    Overrides,
+    /// Inheritance edges, i.e. one per base class of each class.
+    BaseClasses,
+    Namespaces,
+    ComptimeBlocks,
+    ComptimeParameters,
+    Defers,
+    ErrDefers,
+    ErrorSets,
+    Tries,
+    Imports,
+    CImports,
+    Comprehensions,
+    Decorators,
+    /// Python variables assigned at module scope, captured with `@variable`.
+    ModuleVariables,
+    /// Python variables assigned inside functions, captured with `@variable`.
+    LocalVariables,
+    /// Items that are visible outside of the library, captured with `@public` or with `@header`
+    /// for declarations that only are public in headers.
+    PublicItems,
+    /// Rust items that are only visible inside the crate, like `pub(crate)`.
+    RestrictedItems,
+    /// Public items without a doc comment, counted from the PublicItems matches.
+    UndocumentedItems,
+    /// Imports of other files, the capture name tells what kind of import it is.
+    Dependencies,
+    /// Code that only is used by tests, captured with `@test`.
+    TestCode,
+}
+
+impl QType {
+    /// Whether the operation is reported as the number of lines covered by the matched nodes
+    /// rather than the number of matches.
+    pub fn counts_lines(&self) -> bool {
+        matches!(self, QType::DocComments | QType::UnsafeLines)
+    }
+
+    /// Whether the operation is only used to calculate other metrics and shouldn't be reported.
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self,
+            QType::Decisions | QType::Nesting | QType::TestCode | QType::Dependencies
+        )
+    }
 }
 
 pub struct Operation {
@@ -80,14 +183,132 @@ pub struct Operation {
     pub query: TS::Query,
 }
 
+/// Lexical rules used to find comments in languages without a tree-sitter grammar.
+pub struct Syntax {
+    /// Markers that start a comment running to the end of the line.
+    pub line_comments: &'static [&'static str],
+    /// Whether line comments need whitespace or the start of the line before the marker, like
+    /// `#` in shell where `$#` isn't a comment.
+    pub spaced_line_comments: bool,
+    /// Markers that only start a comment at the start of a line, after any indentation.
+    pub line_start_comments: &'static [&'static str],
+    /// Start and end delimiters of block comments.
+    pub block_comments: &'static [(&'static str, &'static str)],
+    /// Whether block comments can be nested inside each other.
+    pub nested: bool,
+    /// Start and end delimiters of string literals, whether `\\` escapes the next character and
+    /// whether the string may span lines. Strings that can't span lines end at the line break
+    /// if they aren't closed, like the apostrophe in `it's`. Delimiters are tried in order so
+    /// longer ones (like `"""`) must come first.
+    pub strings: &'static [(&'static str, &'static str, bool, bool)],
+}
+
 pub trait Language: Send + Sync {
     fn matches_filename(&self, filename: &str) -> bool;
     fn name(&self) -> &str;
+    /// Other names than `name` that are used for the language in shebangs and modelines.
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Check if `name` is the name or one of the aliases of the language, ignoring case.
+    fn is_called(&self, name: &str) -> bool {
+        self.name().eq_ignore_ascii_case(name)
+            || self.aliases().iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
     fn language(&self) -> Option<TS::Language> {
         None
     }
+    fn syntax(&self) -> Option<&'static Syntax> {
+        None
+    }
     fn queries(&self) -> &'static Vec<Operation> {
         static V: Vec<Operation> = vec![];
-        return &V;
+        &V
+    }
+}
+
+/// Find the name of the language in a shebang or in a Vim or Emacs modeline, e.g. `python` for
+/// `#!/usr/bin/env python3`. Version numbers are removed and the name is lowercase.
+pub fn content_language(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let name = lines
+        .first()
+        .and_then(|l| l.strip_prefix("#!"))
+        .and_then(shebang_interpreter)
+        .or_else(|| {
+            // Vim looks for modelines in the first and last five lines.
+            let last = lines.len().saturating_sub(5).max(5);
+            lines
+                .iter()
+                .take(5)
+                .chain(lines.iter().skip(last))
+                .find_map(|l| emacs_mode(l).or_else(|| vim_filetype(l)))
+        })?;
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    (!name.is_empty()).then(|| name.to_lowercase())
+}
+
+fn shebang_interpreter(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program != "env" {
+        return Some(program);
+    }
+    // Skip options and variables given to env.
+    words.find(|w| !w.starts_with('-') && !w.contains('='))
+}
+
+fn emacs_mode(line: &str) -> Option<&str> {
+    let start = line.find("-*-")? + 3;
+    let end = start + line[start..].find("-*-")?;
+    let settings = &line[start..end];
+    if !settings.contains(':') {
+        return Some(settings.trim());
+    }
+    settings.split(';').find_map(|s| {
+        let (key, value) = s.split_once(':')?;
+        (key.trim().eq_ignore_ascii_case("mode")).then(|| value.trim())
+    })
+}
+
+fn vim_filetype(line: &str) -> Option<&str> {
+    let start = ["vim:", "vi:", "ex:"]
+        .iter()
+        .filter_map(|m| {
+            line.match_indices(m)
+                .find(|(i, _)| *i == 0 || line[..*i].ends_with(char::is_whitespace))
+                .map(|(i, _)| i + m.len())
+        })
+        .min()?;
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|s| {
+            s.strip_prefix("ft=")
+                .or_else(|| s.strip_prefix("filetype="))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn languages_in_content() {
+        let lang = |c: &str| content_language(c);
+        assert_eq!(lang("#!/usr/bin/env python3\n").as_deref(), Some("python"));
+        assert_eq!(
+            lang("#!/usr/bin/env -S node --flag\n").as_deref(),
+            Some("node")
+        );
+        assert_eq!(lang("#!/bin/bash -e\necho\n").as_deref(), Some("bash"));
+        assert_eq!(lang("// vim: set ts=4 ft=zig:\n").as_deref(), Some("zig"));
+        assert_eq!(lang("x\n# vim: filetype=sh\n").as_deref(), Some("sh"));
+        assert_eq!(
+            lang("// -*- mode: c++; indent-tabs-mode: nil -*-\n").as_deref(),
+            Some("c++")
+        );
+        assert_eq!(lang("# -*- Python -*-\n").as_deref(), Some("python"));
+        assert_eq!(lang("let review = 1; // vim:\n"), None);
+        assert_eq!(lang("print('hi')\n"), None);
     }
 }
@@ -1,31 +1,231 @@
 use crate::lang_struct;
 use crate::language_utils::Language;
+use crate::language_utils::Syntax;
 
 use tree_sitter as TS;
 
+const JSON: Syntax = Syntax {
+    line_comments: &[],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[],
+    nested: false,
+    strings: &[("\"", "\"", true, false)],
+};
+const MAKE: Syntax = Syntax {
+    line_comments: &["#"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[],
+    nested: false,
+    strings: &[],
+};
+const CMAKE: Syntax = Syntax {
+    line_comments: &["#"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[("#[[", "]]")],
+    nested: false,
+    strings: &[("\"", "\"", true, true), ("[[", "]]", false, true)],
+};
+const MARKUP: Syntax = Syntax {
+    line_comments: &[],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[("<!--", "-->")],
+    nested: false,
+    strings: &[],
+};
+const TOML: Syntax = Syntax {
+    line_comments: &["#"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[],
+    nested: false,
+    strings: &[
+        ("\"\"\"", "\"\"\"", true, true),
+        ("'''", "'''", false, true),
+        ("\"", "\"", true, false),
+        ("'", "'", false, false),
+    ],
+};
+const YAML: Syntax = Syntax {
+    line_comments: &["#"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[],
+    nested: false,
+    strings: &[("\"", "\"", true, false), ("'", "'", false, false)],
+};
+const JAVA: Syntax = Syntax {
+    line_comments: &["//"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[("/*", "*/")],
+    nested: false,
+    strings: &[
+        ("\"\"\"", "\"\"\"", true, true),
+        ("\"", "\"", true, false),
+        ("'", "'", true, false),
+    ],
+};
+const JAVASCRIPT: Syntax = Syntax {
+    line_comments: &["//"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[("/*", "*/")],
+    nested: false,
+    strings: &[
+        ("\"", "\"", true, false),
+        ("'", "'", true, false),
+        ("`", "`", true, true),
+    ],
+};
+const LUA: Syntax = Syntax {
+    line_comments: &["--"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[("--[[", "]]")],
+    nested: false,
+    strings: &[
+        ("\"", "\"", true, false),
+        ("'", "'", true, false),
+        ("[[", "]]", false, true),
+    ],
+};
+const VIM: Syntax = Syntax {
+    line_comments: &[],
+    spaced_line_comments: false,
+    line_start_comments: &["\""],
+    block_comments: &[],
+    nested: false,
+    strings: &[("'", "'", false, false), ("\"", "\"", true, false)],
+};
+const SHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    spaced_line_comments: true,
+    line_start_comments: &[],
+    block_comments: &[],
+    nested: false,
+    strings: &[("\"", "\"", true, false), ("'", "'", false, false)],
+};
+const GO: Syntax = Syntax {
+    line_comments: &["//"],
+    spaced_line_comments: false,
+    line_start_comments: &[],
+    block_comments: &[("/*", "*/")],
+    nested: false,
+    strings: &[
+        ("\"", "\"", true, false),
+        ("'", "'", true, false),
+        ("`", "`", false, true),
+    ],
+};
+
+/// Doxygen style `/** */`, `/*! */`, `///` and `//!` comments.
+const DOXYGEN: &str =
+    r#"((comment) @doc (#match? @doc "^(///([^/]|$)|//!|/[*][*]([^*/]|$)|/[*]!)"))"#;
+
+/// Function definitions in C and C++ with their parameter list, which is found in a function
+/// declarator that can be wrapped in e.g. a pointer declarator.
+const C_FUNCTIONS: &str = r#"
+    (function_definition
+        declarator: [
+            (function_declarator parameters: (parameter_list) @parameters)
+            (_ (function_declarator parameters: (parameter_list) @parameters))
+        ]?) @function
+"#;
+
+/// Includes in C and C++, split by how the included file is found.
+const INCLUDES: &str = r#"
+    (preproc_include path: (string_literal) @include)
+    (preproc_include path: (system_lib_string) @system_include)
+"#;
+
+/// Items in a header that are public unless they are static, checked when counting.
+const C_HEADER_ITEMS: &str = r#"
+    [
+        (declaration)
+        (function_definition)
+        (type_definition)
+        (struct_specifier body: (_))
+        (enum_specifier body: (_))
+        (union_specifier body: (_))
+    ] @header
+"#;
+
+const CPP_HEADER_ITEMS: &str = r#"
+    [
+        (declaration)
+        (function_definition)
+        (type_definition)
+        (alias_declaration)
+        (template_declaration)
+        (class_specifier body: (_))
+        (struct_specifier body: (_))
+        (enum_specifier body: (_))
+        (union_specifier body: (_))
+    ] @header
+"#;
+
+/// Rust items and fields with a visibility like `pub` or `pub(crate)`.
+const RUST_VISIBLE_ITEMS: &str = r#"
+    [
+        (function_item (visibility_modifier) @visibility)
+        (function_signature_item (visibility_modifier) @visibility)
+        (struct_item (visibility_modifier) @visibility)
+        (enum_item (visibility_modifier) @visibility)
+        (union_item (visibility_modifier) @visibility)
+        (trait_item (visibility_modifier) @visibility)
+        (type_item (visibility_modifier) @visibility)
+        (const_item (visibility_modifier) @visibility)
+        (static_item (visibility_modifier) @visibility)
+        (mod_item (visibility_modifier) @visibility)
+        (field_declaration (visibility_modifier) @visibility)
+    ]
+"#;
+
+/// GoogleTest test cases, which are parsed as function definitions named e.g. `TEST`.
+const GTEST_FUNCTIONS: &str = r#"
+    ((function_definition
+        declarator: (function_declarator declarator: (identifier) @name)) @test
+        (#match? @name "^(TEST|TEST_F|TEST_P|TYPED_TEST|TYPED_TEST_P)$"))
+"#;
+
+/// The declarator of a method declaration, function pointer members have a parenthesized
+/// declarator instead of a name.
+const CPP_METHOD_DECLARATOR: &str = r#"
+    (function_declarator
+        declarator: [(field_identifier) (identifier) (destructor_name) (operator_name)])
+"#;
+
+/// Python assignments to names, assignments to attributes and subscripts don't create variables.
+const PYTHON_ASSIGNMENTS: &str = r#"
+    (assignment left: [(identifier) (pattern_list) (tuple_pattern) (list_pattern)]) @variable
+"#;
+
 pub fn languages() -> Vec<Box<dyn Language>> {
     let mut l: Vec<Box<dyn Language>> = vec![];
-    lang_struct!(l, Json, ".json");
-    lang_struct!(l, Makefile, "Makefile", "makefile", ".make");
-    lang_struct!(l, CMake, "CMakeLists.txt", ".cmake");
-    lang_struct!(l, Ninja, ".ninja");
-    lang_struct!(l, Markdown, ".md");
+    lang_struct!(l, Json, syntax JSON, ".json");
+    lang_struct!(l, Makefile, aliases ("make"), syntax MAKE, "Makefile", "makefile", ".make");
+    lang_struct!(l, CMake, syntax CMAKE, "CMakeLists.txt", ".cmake");
+    lang_struct!(l, Ninja, syntax MAKE, ".ninja");
+    lang_struct!(l, Markdown, syntax MARKUP, ".md");
     lang_struct!(l, Text, ".txt");
-    lang_struct!(l, Toml, ".toml");
-    lang_struct!(l, Xml, ".xml");
-    lang_struct!(l, Yaml, ".yaml", ".yml");
+    lang_struct!(l, Toml, syntax TOML, ".toml");
+    lang_struct!(l, Xml, syntax MARKUP, ".xml");
+    lang_struct!(l, Yaml, syntax YAML, ".yaml", ".yml");
 
-    lang_struct!(l, Python, ".py");
-    lang_struct!(l, Java, ".java");
-    lang_struct!(l, JavaScript, ".js");
-    lang_struct!(l, TypeScript, ".ts");
-    lang_struct!(l, Lua, ".lua");
-    lang_struct!(l, Vim, ".vim");
-    lang_struct!(l, Shell, ".sh");
-    lang_struct!(l, Bash, ".bash");
-    lang_struct!(l, Zsh, ".zsh");
-    lang_struct!(l, Fish, ".fish");
-    lang_struct!(l, Go, ".go");
+    lang_struct!(l, Java, syntax JAVA, ".java");
+    lang_struct!(l, JavaScript, aliases ("node", "js"), syntax JAVASCRIPT, ".js");
+    lang_struct!(l, TypeScript, syntax JAVASCRIPT, ".ts");
+    lang_struct!(l, Lua, syntax LUA, ".lua");
+    lang_struct!(l, Vim, syntax VIM, ".vim");
+    lang_struct!(l, Shell, aliases ("sh", "dash", "ksh"), syntax SHELL, ".sh");
+    lang_struct!(l, Bash, syntax SHELL, ".bash");
+    lang_struct!(l, Zsh, syntax SHELL, ".zsh");
+    lang_struct!(l, Fish, syntax SHELL, ".fish");
+    lang_struct!(l, Go, syntax GO, ".go");
 
     use crate::language_utils::Operation;
     use crate::language_utils::QType::*;
@@ -34,36 +234,286 @@ pub fn languages() -> Vec<Box<dyn Language>> {
         ending ".rs",
         ts tree_sitter_rust,
         Loops; "(for_expression) (while_expression) (loop_expression)",
-        Functions; "(function_item)",
-        Variables; "(let_declaration) (const_item) (static_item)"
+        Functions; "(function_item parameters: (parameters) @parameters) @function",
+        Variables; "(let_declaration) (const_item) (static_item)",
+        Comments; "(line_comment) @comment (block_comment) @comment",
+        DocComments; "(line_comment doc: (doc_comment)) @doc (block_comment doc: (doc_comment)) @doc",
+        Decisions; r#"
+            (if_expression) @decision
+            ((match_arm) @decision (#not-match? @decision "^_\\s*=>"))
+            (while_expression) @decision
+            (for_expression) @decision
+            (binary_expression) @decision
+            (try_expression) @decision
+        "#,
+        Nesting; r#"
+            (if_expression) @branch
+            (match_expression) @branch
+            (while_expression) @branch
+            (for_expression) @branch
+            (loop_expression) @branch
+            (closure_expression) @nest
+            (else_clause) @flow
+            (break_expression (label)) @flow
+            (continue_expression (label)) @flow
+            (binary_expression) @logical
+            (block) @block
+            (match_block) @block
+        "#,
+        UnsafeBlocks; "(unsafe_block)",
+        UnsafeLines; "(unsafe_block) @unsafe",
+        UnsafeFunctions; r#"
+            (function_item (function_modifiers "unsafe"))
+            (function_signature_item (function_modifiers "unsafe"))
+        "#,
+        UnsafeImpls; r#"(impl_item "unsafe")"#,
+        UnsafeTraits; r#"(trait_item "unsafe")"#,
+        ExternBlocks; "(foreign_mod_item)",
+        RawPointerDerefs; r#"(unary_expression "*") @deref"#,
+        TestFunctions; r#"
+            ((attribute_item) @attr . (attribute_item)* . (function_item)
+                (#match? @attr "^#\\[([a-z_]+::)*test\\]$"))
+        "#,
+        TestCode; r#"
+            ((attribute_item) @attr @test . (attribute_item)* @test . (function_item) @test
+                (#match? @attr "^#\\[([a-z_]+::)*test\\]$"))
+            ((attribute_item) @attr @test . (mod_item) @test
+                (#match? @attr "^#\\[cfg[(]test[)]\\]$"))
+        "#,
+        Structs; "(struct_item)",
+        Enums; "(enum_item)",
+        Unions; "(union_item)",
+        Traits; "(trait_item)",
+        InherentImpls; "(impl_item !trait)",
+        TraitImpls; "(impl_item trait: (_))",
+        TypeAliases; "(type_item)",
+        Macros; "(macro_definition)",
+        Modules; "(mod_item)",
+        Uses; "(use_declaration)",
+        PublicItems; &format!(r#"({} @public (#eq? @visibility "pub"))"#, RUST_VISIBLE_ITEMS),
+        RestrictedItems; &format!(r#"({} (#not-eq? @visibility "pub"))"#, RUST_VISIBLE_ITEMS),
+        Dependencies; r#"
+            (mod_item !body name: (identifier) @module)
+            (use_declaration argument: (_) @use)
+            (extern_crate_declaration name: (identifier) @crate)
+        "#
     );
     lang_struct!(l,
         Cpp,
-        endings (".cpp", ".hpp", ".cc", ".hh"),
+        endings (".cpp", ".hpp", ".cc", ".hh", ".cxx", ".hxx"),
+        aliases ("c++"),
         ts tree_sitter_cpp,
         Loops; "(for_range_loop) (for_statement) (while_statement) (do_statement)",
-        Functions; "(function_definition)",
+        Functions; C_FUNCTIONS,
         Variables; "(declaration)",
         Templates; "(template_declaration)",
-        Defines; "(preproc_def)"
+        Defines; "(preproc_def)",
+        Comments; "(comment) @comment",
+        DocComments; DOXYGEN,
+        Decisions; r#"
+            (if_statement) @decision
+            (for_statement) @decision
+            (for_range_loop) @decision
+            (while_statement) @decision
+            (do_statement) @decision
+            (case_statement value: (_)) @decision
+            (catch_clause) @decision
+            (conditional_expression) @decision
+            (binary_expression) @decision
+        "#,
+        Nesting; r#"
+            (if_statement) @branch
+            (switch_statement) @branch
+            (for_statement) @branch
+            (for_range_loop) @branch
+            (while_statement) @branch
+            (do_statement) @branch
+            (catch_clause) @branch
+            (conditional_expression) @branch
+            (lambda_expression) @nest
+            (else_clause) @flow
+            (goto_statement) @flow
+            (binary_expression) @logical
+            (compound_statement) @block
+        "#,
+        TestFunctions; GTEST_FUNCTIONS,
+        TestCode; GTEST_FUNCTIONS,
+        Classes; "(class_specifier body: (_)) @class (struct_specifier body: (_)) @class",
+        Methods; &format!(
+            r#"
+                (field_declaration_list [
+                    (function_definition)
+                    (field_declaration declarator: [{0} (_ {0})])
+                    (declaration declarator: [{0} (_ {0})])
+                    (template_declaration [(function_definition) (declaration)])
+                ] @method)
+            "#,
+            CPP_METHOD_DECLARATOR
+        ),
+        VirtualFunctions; r#"
+            (function_definition "virtual")
+            (field_declaration "virtual")
+            (declaration "virtual")
+        "#,
+        PureVirtualFunctions; r#"
+            (field_declaration "virtual" default_value: (number_literal) @zero (#eq? @zero "0"))
+        "#,
+        Overrides; r#"((virtual_specifier) @specifier (#eq? @specifier "override"))"#,
+        BaseClasses; r#"
+            (base_class_clause [(type_identifier) (qualified_identifier) (template_type)] @base)
+        "#,
+        Namespaces; "(namespace_definition)",
+        PublicItems; CPP_HEADER_ITEMS,
+        Dependencies; INCLUDES
     );
     lang_struct!(l,
         C,
         endings (".c", ".h"),
         ts tree_sitter_c,
         Loops; "(for_statement) (while_statement) (do_statement)",
-        Functions; "(function_definition)",
-        Variables; "(declaration)"
+        Functions; C_FUNCTIONS,
+        Variables; "(declaration)",
+        Comments; "(comment) @comment",
+        DocComments; DOXYGEN,
+        Decisions; r#"
+            (if_statement) @decision
+            (for_statement) @decision
+            (while_statement) @decision
+            (do_statement) @decision
+            (case_statement value: (_)) @decision
+            (conditional_expression) @decision
+            (binary_expression) @decision
+        "#,
+        Nesting; r#"
+            (if_statement) @branch
+            (switch_statement) @branch
+            (for_statement) @branch
+            (while_statement) @branch
+            (do_statement) @branch
+            (conditional_expression) @branch
+            (else_clause) @flow
+            (goto_statement) @flow
+            (binary_expression) @logical
+            (compound_statement) @block
+        "#,
+        PublicItems; C_HEADER_ITEMS,
+        Dependencies; INCLUDES
     );
     lang_struct!(l,
         Zig,
         ending ".zig",
         ts tree_sitter_zig,
         Loops; "(for_statement) (for_expression) (while_statement) (while_expression)",
-        Functions; "(function_declaration)",
-        Variables; "(variable_declaration)"
+        Functions; r#"
+            (function_declaration (parameters) @parameters) @function
+            (test_declaration) @function
+        "#,
+        Variables; "(variable_declaration)",
+        Comments; "(comment) @comment",
+        DocComments; r#"((comment) @doc (#match? @doc "^//[/!]([^/]|$)"))"#,
+        Decisions; r#"
+            (if_statement) @decision
+            (if_expression) @decision
+            (for_statement) @decision
+            (for_expression) @decision
+            (while_statement) @decision
+            (while_expression) @decision
+            ((switch_case) @decision (#not-match? @decision "^else\\s*=>"))
+            (binary_expression) @decision
+            (try_expression) @decision
+            (catch_expression) @decision
+        "#,
+        Nesting; r#"
+            (if_statement) @branch
+            (if_expression) @branch
+            (switch_expression) @branch
+            (for_statement) @branch
+            (for_expression) @branch
+            (while_statement) @branch
+            (while_expression) @branch
+            (catch_expression) @branch
+            (else_clause) @flow
+            (binary_expression) @logical
+            (block) @block
+        "#,
+        TestFunctions; "(test_declaration)",
+        TestCode; "(test_declaration) @test",
+        ComptimeBlocks; "(comptime_statement) (comptime_declaration)",
+        ComptimeParameters; r#"(parameter "comptime")"#,
+        Defers; "(defer_statement)",
+        ErrDefers; "(errdefer_statement)",
+        ErrorSets; "(error_set_declaration)",
+        Tries; "(try_expression)",
+        Imports; r#"(builtin_function (builtin_identifier) @name (#eq? @name "@import"))"#,
+        CImports; r#"(builtin_function (builtin_identifier) @name (#eq? @name "@cImport"))"#,
+        Structs; "(struct_declaration)",
+        Enums; "(enum_declaration)",
+        Unions; "(union_declaration)",
+        PublicItems; r#"[(function_declaration "pub") (variable_declaration "pub")] @public"#,
+        Dependencies; r#"
+            (builtin_function
+                (builtin_identifier) @name
+                (arguments (string (string_content) @import))
+                (#eq? @name "@import"))
+        "#
+    );
+    lang_struct!(l,
+        Python,
+        endings (".py", ".pyi", ".pyw"),
+        ts tree_sitter_python,
+        Loops; "(for_statement) (while_statement)",
+        Functions; r#"
+            (function_definition parameters: (parameters) @parameters) @function
+            (lambda (lambda_parameters)? @parameters) @function
+        "#,
+        Variables; PYTHON_ASSIGNMENTS,
+        Comments; "(comment) @comment",
+        Decisions; r#"
+            (if_statement) @decision
+            (elif_clause) @decision
+            (for_statement) @decision
+            (while_statement) @decision
+            (except_clause) @decision
+            ((case_clause) @decision (#not-match? @decision "^case\\s+_\\s*(:|if\\b)"))
+            (conditional_expression) @decision
+            (boolean_operator) @decision
+            (if_clause) @decision
+        "#,
+        Nesting; r#"
+            (if_statement) @branch
+            (match_statement) @branch
+            (for_statement) @branch
+            (while_statement) @branch
+            (except_clause) @branch
+            (conditional_expression) @branch
+            (lambda) @nest
+            (elif_clause) @flow
+            (else_clause) @flow
+            (boolean_operator) @logical
+            (block) @block
+        "#,
+        Classes; "(class_definition) @class",
+        Methods; r#"
+            (class_definition body: (block [
+                (function_definition)
+                (decorated_definition definition: (function_definition))
+            ] @method))
+        "#,
+        BaseClasses; r#"
+            (class_definition superclasses: (argument_list [(identifier) (attribute) (subscript)] @base))
+        "#,
+        Comprehensions; r#"
+            (list_comprehension)
+            (set_comprehension)
+            (dictionary_comprehension)
+            (generator_expression)
+        "#,
+        Imports; "(import_statement) (import_from_statement) (future_import_statement)",
+        Decorators; "(decorator)",
+        ModuleVariables; PYTHON_ASSIGNMENTS,
+        LocalVariables; PYTHON_ASSIGNMENTS
     );
 
     lang_struct!(l, Other, "");
-    return l;
+    l
 }
@@ -1,20 +1,91 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod classes;
+mod complexity;
+mod dependencies;
+mod distribution;
+mod duplication;
+mod encoding;
+mod halstead;
 mod language_utils;
 mod languages;
+mod scanner;
+mod todos;
 
 use clap::{Arg, Command};
+use classes::Class;
 use language_utils::Language;
 use std::collections::hash_map::HashMap;
+use std::collections::HashSet;
 use std::fs;
+use std::ops::Range;
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::thread;
 use streaming_iterator::StreamingIterator;
 use tree_sitter as TS;
 
+use dependencies::{FileDeps, Import, ImportKind};
+use distribution::Distribution;
+use duplication::{Duplicate, FileTokens};
+use halstead::Halstead;
 use language_utils::QType;
+use todos::Todo;
+
+/// Options that affect how files are parsed.
+#[derive(Default)]
+struct Options {
+    /// Collect the tokens of each file to find duplicated code.
+    duplicates: bool,
+    /// Treat all identifiers as the same token when looking for duplicated code.
+    normalize_identifiers: bool,
+    /// Markers like TODO to look for in comments.
+    markers: Vec<String>,
+    /// Collect the imports of each file to build a dependency graph.
+    dependencies: bool,
+    /// Ignore generated files instead of counting them as e.g. "Rust (generated)".
+    skip_generated: bool,
+    /// The directories that are scanned, vendored directories are only looked for below them.
+    roots: Vec<String>,
+    /// Paths of vendored code in addition to the directories in `VENDORED_DIRS`.
+    vendored: Vec<glob::Pattern>,
+    /// Ignore vendored files instead of counting them as e.g. "C (vendored)".
+    skip_vendored: bool,
+    /// Languages to use for files matching a pattern, instead of guessing from the name.
+    language_overrides: Vec<(glob::Pattern, String)>,
+}
+
+#[derive(Clone)]
+struct Function {
+    path: String,
+    name: String,
+    start_line: usize,
+    end_line: usize,
+    parameters: usize,
+    complexity: usize,
+    cognitive: usize,
+    nesting: usize,
+    halstead: Halstead,
+    maintainability: f64,
+}
+
+/// A file where the parse tree contains errors, lines and columns start at 1.
+#[derive(Clone)]
+struct ParseError {
+    path: String,
+    /// The number of error and missing nodes.
+    errors: usize,
+    line: usize,
+    column: usize,
+}
+
+/// Metrics for a whole file.
+#[derive(Clone)]
+struct FileMetrics {
+    halstead: Halstead,
+    maintainability: f64,
+}
 
 #[derive(Clone)]
 struct Stats {
@@ -22,109 +93,1090 @@ struct Stats {
     files: usize,
     total_lines: usize,
     blank_lines: usize,
+    comment_lines: usize,
+    code_lines: usize,
+    /// Code lines in test functions, test modules and files in test directories.
+    test_lines: usize,
     operations: HashMap<QType, usize>,
+    functions: Vec<Function>,
+    file_metrics: Vec<FileMetrics>,
+    tokens: Vec<FileTokens>,
+    todos: Vec<Todo>,
+    classes: Vec<Class>,
+    dependencies: Vec<FileDeps>,
+    parse_errors: Vec<ParseError>,
 }
 
 impl Stats {
     fn new(name: &str) -> Self {
-        let stats = Self {
+        Self {
             name: name.to_string(),
             files: 0,
             total_lines: 0,
             blank_lines: 0,
+            comment_lines: 0,
+            code_lines: 0,
+            test_lines: 0,
             operations: HashMap::new(),
-        };
-
-        return stats;
+            functions: vec![],
+            file_metrics: vec![],
+            tokens: vec![],
+            todos: vec![],
+            classes: vec![],
+            dependencies: vec![],
+            parse_errors: vec![],
+        }
     }
 
-    fn add(&mut self, other: &Stats) {
+    fn add(&mut self, mut other: Stats) {
         assert!(self.name == other.name);
+        self.merge(&other);
+        self.functions.append(&mut other.functions);
+        self.file_metrics.append(&mut other.file_metrics);
+        self.tokens.append(&mut other.tokens);
+        self.todos.append(&mut other.todos);
+        self.classes.append(&mut other.classes);
+        self.dependencies.append(&mut other.dependencies);
+        self.parse_errors.append(&mut other.parse_errors);
+    }
+
+    /// Add all counters from `other`, also used to sum up the different languages into a total.
+    /// The collected items like functions and tokens are only kept per language and are moved
+    /// by `add`.
+    fn merge(&mut self, other: &Stats) {
         self.files += other.files;
         self.total_lines += other.total_lines;
         self.blank_lines += other.blank_lines;
+        self.comment_lines += other.comment_lines;
+        self.code_lines += other.code_lines;
+        self.test_lines += other.test_lines;
         for (k, v) in &other.operations {
-            if self.operations.contains_key(&k) {
-                *self.operations.get_mut(&k).unwrap() += v;
+            if self.operations.contains_key(k) {
+                *self.operations.get_mut(k).unwrap() += v;
             } else {
                 self.operations.insert(k.clone(), *v);
             }
         }
     }
 
-    fn update(&mut self, content: &str, language: &Box<dyn Language>) {
+    fn update(
+        &mut self,
+        filename: &str,
+        content: &str,
+        language: &dyn Language,
+        options: &Options,
+    ) {
+        let mut comments = vec![];
+        let mut functions = vec![];
+        let mut parameters = vec![];
+        let mut decisions = vec![];
+        let mut structures = HashMap::new();
+        let mut test_rows = HashSet::new();
+        let mut classes = vec![];
+        let mut methods = vec![];
+        let mut imports = vec![];
+        let functions_before = self.functions.len();
+        let mut file_halstead = None;
+        let mut file_complexity = 1;
+        let test_file = is_test_path(filename);
         if let Some(lang) = language.language() {
             let mut parser = TS::Parser::new();
             parser.set_language(&lang).unwrap();
 
-            let tree = parser.parse(&content, None).unwrap();
+            let tree = parser.parse(content, None).unwrap();
             let root_node = tree.root_node();
+            if let Some(first) = first_error(&root_node) {
+                self.parse_errors.push(ParseError {
+                    path: filename.to_string(),
+                    errors: parse_errors(&root_node),
+                    line: first.row + 1,
+                    column: first.column + 1,
+                });
+            }
 
             let mut query_cursor = TS::QueryCursor::new();
 
             for query in language.queries() {
-                let matches = query_cursor
-                    .matches(&query.query, root_node, content.as_bytes())
-                    .count();
-                let count = self.operations.get(&query.qtype).unwrap_or(&0);
-                self.operations.insert(query.qtype.clone(), count + matches);
+                let mut matches = query_cursor.matches(&query.query, root_node, content.as_bytes());
+                let mut count = 0;
+                let mut undocumented = 0;
+                let mut lines = HashSet::new();
+                while let Some(m) = matches.next() {
+                    if !counted(
+                        &query.qtype,
+                        m,
+                        query.query.capture_names(),
+                        filename,
+                        content,
+                    ) {
+                        continue;
+                    }
+                    count += 1;
+                    if query.qtype == QType::PublicItems && !documented(m, &query.query, content) {
+                        undocumented += 1;
+                    }
+                    if query.qtype == QType::Functions {
+                        let capture = |name: &str| {
+                            m.captures
+                                .iter()
+                                .find(|c| query.query.capture_names()[c.index as usize] == name)
+                                .map(|c| c.node)
+                        };
+                        if let Some(function) = capture("function") {
+                            functions.push(function);
+                            parameters.push(capture("parameters"));
+                        }
+                    }
+                    for c in m.captures {
+                        match query.qtype {
+                            QType::Comments => comments.push(c.node.byte_range()),
+                            QType::Decisions => decisions.push(c.node),
+                            QType::Classes => classes.push(c.node),
+                            QType::Methods => methods.push(c.node),
+                            QType::Dependencies => {
+                                let name = query.query.capture_names()[c.index as usize];
+                                if let Some(kind) = ImportKind::from_capture(name) {
+                                    imports.push(Import::from_node(kind, &c.node, content));
+                                }
+                            }
+                            QType::TestCode
+                                if query.query.capture_names()[c.index as usize] == "test" =>
+                            {
+                                test_rows.extend(node_lines(&c.node))
+                            }
+                            QType::Nesting => {
+                                let name = query.query.capture_names()[c.index as usize];
+                                if let Some(s) = complexity::Structure::from_capture(name) {
+                                    structures.insert(c.node.id(), s);
+                                }
+                            }
+                            _ => {}
+                        }
+                        if query.qtype.counts_lines() {
+                            lines.extend(node_lines(&c.node));
+                        }
+                    }
+                }
+                if query.qtype.counts_lines() {
+                    count = lines.len();
+                }
+                if query.qtype.is_internal() || (test_file && query.qtype == QType::TestFunctions) {
+                    continue;
+                }
+                let n = self.operations.get(&query.qtype).unwrap_or(&0);
+                self.operations.insert(query.qtype.clone(), n + count);
+                if query.qtype == QType::PublicItems {
+                    *self.operations.entry(QType::UndocumentedItems).or_insert(0) += undocumented;
+                }
             }
+            if test_file {
+                // All functions in a test file are test functions, not only the ones found by
+                // the TestFunctions query.
+                let n = self.operations.entry(QType::TestFunctions).or_insert(0);
+                *n += functions.len();
+            }
+
+            let cyclomatic = complexity::cyclomatic(&functions, &decisions);
+            let cognitive = complexity::cognitive(&functions, &structures);
+            for (i, (complexity, (cognitive, nesting))) in
+                cyclomatic.into_iter().zip(cognitive).enumerate()
+            {
+                file_complexity += complexity - 1;
+                let lines = node_lines(&functions[i]);
+                let halstead = Halstead::new(&functions[i], content);
+                let maintainability = halstead::maintainability_index(
+                    halstead.volume(),
+                    complexity,
+                    lines.clone().count(),
+                );
+                self.functions.push(Function {
+                    path: filename.to_string(),
+                    name: function_name(&functions[i], content),
+                    start_line: lines.start() + 1,
+                    end_line: lines.end() + 1,
+                    parameters: parameters[i].map_or(0, |p| parameter_count(&p, content)),
+                    complexity,
+                    cognitive,
+                    nesting,
+                    halstead,
+                    maintainability,
+                });
+            }
+            self.classes.extend(
+                classes
+                    .iter()
+                    .map(|c| Class::new(filename, c, content, &methods)),
+            );
+            file_halstead = Some(Halstead::new(&root_node, content));
+            if options.duplicates {
+                self.tokens.push(FileTokens {
+                    path: filename.to_string(),
+                    tokens: duplication::tokenize(
+                        &root_node,
+                        content,
+                        options.normalize_identifiers,
+                    ),
+                });
+            }
+        } else if let Some(syntax) = language.syntax() {
+            comments = scanner::comments(content, syntax);
         }
 
         self.files += 1;
-        for line in content.lines() {
+        self.todos.extend(todos::find_todos(
+            filename,
+            content,
+            &comments,
+            &options.markers,
+        ));
+        let code_lines = self.code_lines;
+        let total_lines = self.total_lines;
+        self.count_lines(content, &comments, |row| {
+            test_file || test_rows.contains(&row)
+        });
+        if options.dependencies && language.language().is_some() {
+            self.dependencies.push(FileDeps {
+                path: filename.to_string(),
+                language: self.name.clone(),
+                lines: self.total_lines - total_lines,
+                functions: self.functions.len() - functions_before,
+                imports,
+            });
+        }
+
+        if let Some(halstead) = file_halstead {
+            let maintainability = halstead::maintainability_index(
+                halstead.volume(),
+                file_complexity,
+                self.code_lines - code_lines,
+            );
+            self.file_metrics.push(FileMetrics {
+                halstead,
+                maintainability,
+            });
+        }
+    }
+
+    /// Classify each line as blank, comment or code. A line is only a comment line if all
+    /// non-whitespace characters on it are inside one of the `comments` byte ranges, so a line
+    /// with code followed by a trailing comment counts as code. `is_test` tells if a row is test
+    /// code.
+    fn count_lines(
+        &mut self,
+        content: &str,
+        comments: &[Range<usize>],
+        is_test: impl Fn(usize) -> bool,
+    ) {
+        let mut in_comment = vec![false; content.len()];
+        for c in comments {
+            in_comment[c.clone()].fill(true);
+        }
+
+        let mut start = 0;
+        for (row, line) in content.split_inclusive('\n').enumerate() {
+            let range = start..start + line.len();
+            start = range.end;
             self.total_lines += 1;
             if line.trim().is_empty() {
                 self.blank_lines += 1;
+                continue;
+            }
+            let has_code = content.as_bytes()[range.clone()]
+                .iter()
+                .zip(&in_comment[range])
+                .any(|(b, c)| !c && !b.is_ascii_whitespace());
+            if has_code {
+                self.code_lines += 1;
+                if is_test(row) {
+                    self.test_lines += 1;
+                }
+            } else {
+                self.comment_lines += 1;
             }
         }
     }
 
-    fn print_header() {
+    fn print_header(columns: &[String], name_width: usize) {
+        print!(
+            "{:name_width$}{:15}{:15}{:15}{:15}{:15}",
+            "Language", "Files", "Total lines", "Blank lines", "Comment lines", "Code lines",
+        );
+        for c in columns {
+            print!("{:15}", c);
+        }
+        println!();
         println!(
-            "{:15}{:15}{:15}{:15}{:15}{:15}{:15}",
-            "Language", "Files", "Total lines", "Blank lines", "Functions", "Variables", "Loops",
+            "{:=<width$}",
+            "",
+            width = name_width + (5 + columns.len()) * 15
         );
-        println!("{:=<width$}", "", width = 7 * 15);
     }
 
-    fn print(&self) {
-        let functions = self
-            .operations
-            .get(&QType::Functions)
-            .map_or("-".to_string(), |n| n.to_string());
-        let variables = self
-            .operations
-            .get(&QType::Variables)
-            .map_or("-".to_string(), |n| n.to_string());
-        let loops = self
-            .operations
-            .get(&QType::Loops)
-            .map_or("-".to_string(), |n| n.to_string());
-        println!(
-            "{:<15}{:<15}{:<15}{:<15}{:<15}{:<15}{:<15}",
-            self.name, self.files, self.total_lines, self.blank_lines, functions, variables, loops,
+    /// Get the count of the operation with the given name, ignoring case.
+    fn operation(&self, name: &str) -> Option<usize> {
+        self.operations
+            .iter()
+            .find(|(k, _)| format!("{:?}", k).eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+    }
+
+    fn print(&self, columns: &[String], name_width: usize) {
+        print!(
+            "{:<name_width$}{:<15}{:<15}{:<15}{:<15}{:<15}",
+            self.name,
+            self.files,
+            self.total_lines,
+            self.blank_lines,
+            self.comment_lines,
+            self.code_lines,
         );
+        for c in columns {
+            let n = self.operation(c).map_or("-".to_string(), |n| n.to_string());
+            print!("{:<15}", n);
+        }
+        println!();
     }
-    fn print_detailed(&self) {
+    fn print_detailed(&self, max_params: usize, markers: &[String]) {
         println!("*** {} ***", self.name);
         println!("Number of files: {}", self.files);
         println!("Total lines: {}", self.total_lines);
         println!("Blank lines: {}", self.blank_lines);
-        for (k, v) in &self.operations {
+        println!("Comment lines: {}", self.comment_lines);
+        println!("Code lines: {}", self.code_lines);
+        if self.test_lines > 0 {
+            println!("Test lines: {}", self.test_lines);
+            println!("Production lines: {}", self.code_lines - self.test_lines);
+        }
+        if let Some(tests) = self
+            .operations
+            .get(&QType::TestFunctions)
+            .filter(|n| **n > 0)
+        {
+            let functions = self.operations.get(&QType::Functions).unwrap_or(&0);
+            println!("Production functions: {}", functions.saturating_sub(*tests));
+        }
+        let mut operations: Vec<_> = self.operations.iter().collect();
+        operations.sort();
+        for (k, v) in operations {
             println!("{:?}: {}", k, v);
         }
+        if let Some(public) = self.operations.get(&QType::PublicItems).filter(|n| **n > 0) {
+            let undocumented = self.operations.get(&QType::UndocumentedItems).unwrap_or(&0);
+            println!(
+                "Documented public items: {:.1}%",
+                100.0 * (public - undocumented) as f64 / *public as f64
+            );
+        }
+        if !self.parse_errors.is_empty() {
+            println!(
+                "Files with parse errors: {}, errors and missing tokens: {}",
+                self.parse_errors.len(),
+                self.parse_errors.iter().map(|e| e.errors).sum::<usize>()
+            );
+        }
+        if !self.todos.is_empty() {
+            println!("Markers in comments:");
+            for marker in markers {
+                let n = self.todos.iter().filter(|t| &t.marker == marker).count();
+                if n > 0 {
+                    println!("  {}: {}", marker, n);
+                }
+            }
+        }
+        if !self.classes.is_empty() {
+            print_metric("Methods per class", self.classes.iter().map(|c| c.methods));
+            let classes: Vec<&Class> = self.classes.iter().collect();
+            print_metric(
+                "Inheritance depth",
+                classes::inheritance_depths(&classes).into_iter(),
+            );
+        }
+        if !self.functions.is_empty() {
+            print_metric(
+                "Cyclomatic complexity",
+                self.functions.iter().map(|f| f.complexity),
+            );
+            print_metric(
+                "Cognitive complexity",
+                self.functions.iter().map(|f| f.cognitive),
+            );
+            print_metric(
+                "Max nesting depth",
+                self.functions.iter().map(|f| f.nesting),
+            );
+            print_metric("Parameters", self.functions.iter().map(|f| f.parameters));
+            println!(
+                "Functions with more than {} parameters: {}",
+                max_params,
+                self.functions
+                    .iter()
+                    .filter(|f| f.parameters > max_params)
+                    .count()
+            );
+
+            let lengths = Distribution::new(self.functions.iter().map(|f| f.lines()));
+            println!(
+                "Function lengths: min {}, max {}, mean {:.1}, median {}, p90 {}, p99 {}",
+                lengths.min(),
+                lengths.max(),
+                lengths.mean(),
+                lengths.median(),
+                lengths.percentile(90),
+                lengths.percentile(99)
+            );
+            let buckets = distribution::bucket_names(&distribution::LENGTH_BUCKETS);
+            let histogram = lengths.histogram(&distribution::LENGTH_BUCKETS);
+            println!("Function length histogram:");
+            for (name, n) in buckets.iter().zip(histogram) {
+                println!("  {:>7} lines: {}", name, n);
+            }
+        }
+        if !self.file_metrics.is_empty() {
+            println!("Halstead metrics (average per file / per function):");
+            self.print_halstead("Vocabulary", |h| h.vocabulary() as f64);
+            self.print_halstead("Length", |h| h.length() as f64);
+            self.print_halstead("Volume", Halstead::volume);
+            self.print_halstead("Difficulty", Halstead::difficulty);
+            self.print_halstead("Effort", Halstead::effort);
+            println!(
+                "Maintainability index (average per file / per function): {:.1} / {:.1}",
+                mean(self.file_metrics.iter().map(|f| f.maintainability)),
+                mean(self.functions.iter().map(|f| f.maintainability))
+            );
+        }
+    }
+
+    fn print_halstead(&self, name: &str, metric: fn(&Halstead) -> f64) {
+        println!(
+            "  {}: {:.1} / {:.1}",
+            name,
+            mean(self.file_metrics.iter().map(|f| metric(&f.halstead))),
+            mean(self.functions.iter().map(|f| metric(&f.halstead)))
+        );
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = values.fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
+    if n == 0 {
+        return 0.0;
+    }
+    sum / n as f64
+}
+
+/// Print the total, average and max of a per-function metric.
+fn print_metric(name: &str, values: impl Iterator<Item = usize>) {
+    let d = Distribution::new(values);
+    println!(
+        "{}: total {}, average {:.1}, max {}",
+        name,
+        d.total(),
+        d.mean(),
+        d.max()
+    );
+}
+
+impl Function {
+    fn lines(&self) -> usize {
+        self.end_line - self.start_line + 1
+    }
+}
+
+/// Print one row per function, sorted by `sort` and limited to the `top` first ones. Functions
+/// with more than `max_params` parameters are marked.
+fn print_functions(stats: &[&Stats], sort: &str, top: Option<usize>, max_params: usize) {
+    let mut functions: Vec<&Function> = stats.iter().flat_map(|s| &s.functions).collect();
+    functions.sort_by(|a, b| (&a.path, a.start_line).cmp(&(&b.path, b.start_line)));
+    match sort {
+        "path" => {}
+        "name" => functions.sort_by(|a, b| a.name.cmp(&b.name)),
+        "params" => functions.sort_by_key(|f| std::cmp::Reverse(f.parameters)),
+        "complexity" => functions.sort_by_key(|f| std::cmp::Reverse(f.complexity)),
+        "cognitive" => functions.sort_by_key(|f| std::cmp::Reverse(f.cognitive)),
+        "nesting" => functions.sort_by_key(|f| std::cmp::Reverse(f.nesting)),
+        _ => functions.sort_by_key(|f| std::cmp::Reverse(f.lines())),
+    }
+
+    println!(
+        "{:8}{:8}{:12}{:12}{:10}{:6}Function",
+        "Lines", "Params", "Complexity", "Cognitive", "Nesting", "MI"
+    );
+    println!("{:=<width$}", "", width = 76);
+    for f in functions.iter().take(top.unwrap_or(functions.len())) {
+        println!(
+            "{:<8}{:<8}{:<12}{:<12}{:<10}{:<6.0}{}:{}-{} {}{}",
+            f.lines(),
+            f.parameters,
+            f.complexity,
+            f.cognitive,
+            f.nesting,
+            f.maintainability,
+            f.path,
+            f.start_line,
+            f.end_line,
+            f.name,
+            if f.parameters > max_params {
+                " (too many parameters)"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+/// Print one row per class with its number of methods, base classes and depth of inheritance.
+fn print_classes(stats: &[&Stats]) {
+    let mut classes: Vec<&Class> = vec![];
+    let mut depths = vec![];
+    for s in stats {
+        let language: Vec<&Class> = s.classes.iter().collect();
+        depths.extend(classes::inheritance_depths(&language));
+        classes.extend(language);
+    }
+    let mut rows: Vec<(&Class, usize)> = classes.into_iter().zip(depths).collect();
+    rows.sort_by(|a, b| (&a.0.path, a.0.line).cmp(&(&b.0.path, b.0.line)));
+
+    println!("{:9}{:7}{:7}Class", "Methods", "Bases", "Depth");
+    println!("{:=<width$}", "", width = 76);
+    for (c, depth) in rows {
+        print!(
+            "{:<9}{:<7}{:<7}{}:{} {}",
+            c.methods,
+            c.bases.len(),
+            depth,
+            c.path,
+            c.line,
+            c.qualified_name
+        );
+        if !c.bases.is_empty() {
+            print!(" : {}", c.bases.join(", "));
+        }
+        println!();
+    }
+}
+
+/// Print the dependencies between the files as `dot`, `json` or a `summary` with the fan-in and
+/// fan-out of the files and the cycles among them.
+fn print_dependencies(stats: &[&Stats], format: &str) {
+    let graph = dependencies::Graph::new(stats.iter().flat_map(|s| &s.dependencies).collect());
+    match format {
+        "dot" => print!("{}", graph.to_dot()),
+        "json" => print!("{}", graph.to_json()),
+        _ => {
+            println!("Files: {}", graph.files.len());
+            println!("Dependencies: {}", graph.edges.len());
+            println!("External or unresolved imports: {}", graph.unresolved);
+            for (name, fan) in [("Fan-in", graph.fan_in()), ("Fan-out", graph.fan_out())] {
+                let Some((i, max)) = fan
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(i, n)| (**n, std::cmp::Reverse(*i)))
+                else {
+                    continue;
+                };
+                println!(
+                    "{}: average {:.1}, max {} ({})",
+                    name,
+                    mean(fan.iter().map(|&n| n as f64)),
+                    max,
+                    graph.files[i].path
+                );
+            }
+            let cycles = graph.cycles();
+            println!("Cycles: {}", cycles.len());
+            for c in cycles {
+                let files: Vec<&str> = c.iter().map(|&i| graph.files[i].path.as_str()).collect();
+                println!("  {}", files.join(" -> "));
+            }
+        }
+    }
+}
+
+/// Print the files with parse errors as `file:line:column` of the first error.
+fn print_parse_errors(stats: &[&Stats]) {
+    let mut errors: Vec<&ParseError> = stats.iter().flat_map(|s| &s.parse_errors).collect();
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+    for e in errors {
+        println!(
+            "{}:{}:{}: {} parse error{}",
+            e.path,
+            e.line,
+            e.column,
+            e.errors,
+            if e.errors == 1 { "" } else { "s" }
+        );
     }
 }
 
+/// Print all markers found in comments as `file:line: text`.
+fn print_todos(stats: &[&Stats]) {
+    let mut todos: Vec<&Todo> = stats.iter().flat_map(|s| &s.todos).collect();
+    todos.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    for t in todos {
+        match &t.author {
+            Some(author) => println!("{}:{}: [{}] {}", t.path, t.line, author, t.text),
+            None => println!("{}:{}: {}", t.path, t.line, t.text),
+        }
+    }
+}
+
+/// Print the duplicated code within each language together with the share of the code lines
+/// that are duplicated.
+/// Get the width of the language column, names like "Python (generated)" are wider than the
+/// other columns.
+fn name_width<'a>(names: impl Iterator<Item = &'a String>) -> usize {
+    names.map(|n| n.len() + 1).fold(15, usize::max)
+}
+
+fn print_duplicates(stats: &[&Stats], duplicates: &HashMap<String, Vec<Duplicate>>) {
+    let mut stats: Vec<&&Stats> = stats
+        .iter()
+        .filter(|s| duplicates.contains_key(&s.name))
+        .collect();
+    stats.sort_by_key(|s| s.name.clone());
+    let duplicates: Vec<&Vec<Duplicate>> = stats.iter().map(|s| &duplicates[&s.name]).collect();
+
+    let name_width = name_width(stats.iter().map(|s| &s.name));
+    println!(
+        "{:name_width$}{:20}{:15}{:15}",
+        "Language", "Duplicated lines", "Code lines", "Duplicated"
+    );
+    println!("{:=<width$}", "", width = name_width + 50);
+    for (s, duplicates) in stats.iter().zip(duplicates.iter().copied()) {
+        let mut lines = HashSet::new();
+        for d in duplicates {
+            for l in [&d.first, &d.second] {
+                lines.extend(l.lines.iter().map(|n| (l.path.as_str(), n)));
+            }
+        }
+        println!(
+            "{:<name_width$}{:<20}{:<15}{:.1}%",
+            s.name,
+            lines.len(),
+            s.code_lines,
+            100.0 * lines.len() as f64 / s.code_lines.max(1) as f64
+        );
+    }
+    println!();
+
+    for d in duplicates.into_iter().flatten() {
+        println!(
+            "{} lines ({} tokens): {}:{}-{} and {}:{}-{}",
+            d.second.lines.len(),
+            d.tokens,
+            d.first.path,
+            d.first.start_line,
+            d.first.end_line,
+            d.second.path,
+            d.second.start_line,
+            d.second.end_line
+        );
+    }
+    println!();
+}
+
+/// Get the name of a function node, for C and C++ the name is found by following the
+/// declarators, e.g. through a pointer declarator to the function declarator. Zig tests are
+/// named by their string.
+fn function_name(node: &TS::Node, content: &str) -> String {
+    let mut node = *node;
+    loop {
+        if let Some(name) = node.child_by_field_name("name") {
+            return content[name.byte_range()].to_string();
+        }
+        let mut cursor = node.walk();
+        let declarator = node.child_by_field_name("declarator").or_else(|| {
+            node.named_children(&mut cursor)
+                .find(|c| c.kind().ends_with("declarator"))
+        });
+        match declarator {
+            Some(d) => node = d,
+            None if node.kind().contains("identifier") => {
+                return content[node.byte_range()].to_string();
+            }
+            None if node.kind() == "test_declaration" => {
+                if let Some(name) = node
+                    .named_children(&mut cursor)
+                    .find(|c| c.kind() != "block")
+                {
+                    return content[name.byte_range()].trim_matches('"').to_string();
+                }
+                return "<anonymous>".to_string();
+            }
+            None => return "<anonymous>".to_string(),
+        }
+    }
+}
+
+/// Count the parameters in a parameter list, a C style `(void)` parameter list counts as empty
+/// and the `*` and `/` separators in Python aren't parameters.
+fn parameter_count(parameters: &TS::Node, content: &str) -> usize {
+    let mut cursor = parameters.walk();
+    parameters
+        .named_children(&mut cursor)
+        .filter(|p| {
+            !p.is_extra() && !p.kind().ends_with("_separator") && &content[p.byte_range()] != "void"
+        })
+        .count()
+}
+
+/// Check the conditions on a match that can't be written in the query.
+fn counted(
+    qtype: &QType,
+    m: &TS::QueryMatch,
+    names: &[&str],
+    filename: &str,
+    content: &str,
+) -> bool {
+    let mut items = m
+        .captures
+        .iter()
+        .filter(|c| matches!(names[c.index as usize], "public" | "header"));
+    let exported = |c: &TS::QueryCapture| {
+        names[c.index as usize] != "header"
+            || (is_header(filename) && in_global_scope(&c.node) && !is_static(&c.node, content))
+    };
+    match qtype {
+        QType::RawPointerDerefs => m.captures.iter().any(|c| in_unsafe(&c.node)),
+        QType::Decisions => m.captures.iter().all(|c| {
+            c.node.kind() != "binary_expression"
+                || matches!(operator(&c.node), "&&" | "||" | "and" | "or" | "orelse")
+        }),
+        QType::Nesting => m.captures.iter().all(|c| {
+            names[c.index as usize] != "logical"
+                || c.node.kind() != "binary_expression"
+                || matches!(operator(&c.node), "&&" | "||" | "and" | "or")
+        }),
+        QType::ModuleVariables => m.captures.iter().all(|c| python_scope(&c.node).is_none()),
+        QType::LocalVariables => m
+            .captures
+            .iter()
+            .all(|c| python_scope(&c.node) == Some("function_definition")),
+        QType::PublicItems => items.all(exported),
+        _ => true,
+    }
+}
+
+/// Check if a PublicItems match has a doc comment on its public item.
+fn documented(m: &TS::QueryMatch, query: &TS::Query, content: &str) -> bool {
+    m.captures
+        .iter()
+        .filter(|c| matches!(query.capture_names()[c.index as usize], "public" | "header"))
+        .any(|c| has_doc_comment(&c.node, content))
+}
+
+/// Get the operator of a binary expression, the operators are checked here rather than in the
+/// queries since alternations on such a common node make the queries slow.
+fn operator<'a>(node: &TS::Node<'a>) -> &'a str {
+    node.child_by_field_name("operator")
+        .map_or("", |o| o.kind())
+}
+
+fn is_header(filename: &str) -> bool {
+    [".h", ".hh", ".hpp", ".hxx"]
+        .iter()
+        .any(|e| filename.ends_with(e))
+}
+
+/// Check if a C or C++ declaration is outside of functions and classes.
+fn in_global_scope(node: &TS::Node) -> bool {
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        if !matches!(
+            p.kind(),
+            "translation_unit"
+                | "preproc_if"
+                | "preproc_ifdef"
+                | "preproc_else"
+                | "preproc_elif"
+                | "declaration_list"
+                | "namespace_definition"
+                | "linkage_specification"
+        ) {
+            return false;
+        }
+        parent = p.parent();
+    }
+    true
+}
+
+/// Get the kind of the innermost Python function or class around a node, `None` at module
+/// scope.
+fn python_scope(node: &TS::Node) -> Option<&'static str> {
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        if matches!(
+            p.kind(),
+            "function_definition" | "lambda" | "class_definition"
+        ) {
+            return Some(p.kind());
+        }
+        parent = p.parent();
+    }
+    None
+}
+
+fn is_static(node: &TS::Node, content: &str) -> bool {
+    let mut cursor = node.walk();
+    let is_static = node
+        .children(&mut cursor)
+        .any(|c| c.kind() == "storage_class_specifier" && &content[c.byte_range()] == "static");
+    is_static
+}
+
+/// Check if a node is preceded by a doc comment, possibly with attributes and other comments in
+/// between.
+fn has_doc_comment(node: &TS::Node, content: &str) -> bool {
+    let mut prev = node.prev_sibling();
+    while let Some(p) = prev {
+        let text = &content[p.byte_range()];
+        if p.kind() == "attribute_item" {
+            if text.starts_with("#[doc") {
+                return true;
+            }
+        } else if p.kind().contains("comment") {
+            let doc = (text.starts_with("///") && !text.starts_with("////"))
+                || (text.starts_with("/**") && !text.starts_with("/***") && text != "/**/")
+                || text.starts_with("//!")
+                || text.starts_with("/*!");
+            if doc {
+                return true;
+            }
+        } else {
+            return false;
+        }
+        prev = p.prev_sibling();
+    }
+    false
+}
+
+/// Check if a file is in a test directory, where all code counts as test code.
+fn is_test_path(filename: &str) -> bool {
+    let path = std::path::Path::new(filename);
+    path.parent().is_some_and(|dir| {
+        dir.components().any(|c| {
+            matches!(
+                c.as_os_str().to_str(),
+                Some("test" | "tests" | "testing" | "unittests")
+            )
+        })
+    })
+}
+
+/// Check if `node` is inside an unsafe block or the body of an unsafe function.
+fn in_unsafe(node: &TS::Node) -> bool {
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        match p.kind() {
+            "unsafe_block" => return true,
+            "function_item" => {
+                let mut cursor = p.walk();
+                return p
+                    .children(&mut cursor)
+                    .filter(|c| c.kind() == "function_modifiers")
+                    .any(|m| (0..m.child_count()).any(|i| m.child(i).unwrap().kind() == "unsafe"));
+            }
+            _ => parent = p.parent(),
+        }
+    }
+    false
+}
+
+/// The lines spanned by a node, not counting the line after a trailing newline.
+fn node_lines(node: &TS::Node) -> std::ops::RangeInclusive<usize> {
+    let start = node.start_position().row;
+    let end = node.end_position();
+    if end.column == 0 && end.row > start {
+        start..=end.row - 1
+    } else {
+        start..=end.row
+    }
+}
+
+/// Markers that tools put at the top of the files they generate.
+const GENERATED_MARKERS: [&str; 5] = [
+    "@generated",
+    "DO NOT EDIT",
+    "automatically generated by rust-bindgen",
+    "Generated by the protocol buffer compiler",
+    "Generated by protoc",
+];
+
+/// Check if a comment in the first lines of a file contains a marker saying that it's generated.
+fn is_generated(language: &dyn Language, content: &str) -> bool {
+    let end = content
+        .match_indices('\n')
+        .nth(9)
+        .map_or(content.len(), |(i, _)| i);
+    let header = &content[..end];
+    header_comments(language, header).into_iter().any(|r| {
+        GENERATED_MARKERS
+            .iter()
+            .any(|m| header[r.clone()].contains(m))
+    })
+}
+
+/// Find the comments in the first lines of a file, without parsing the rest of it.
+fn header_comments(language: &dyn Language, header: &str) -> Vec<Range<usize>> {
+    let mut comments = vec![];
+    if let Some(lang) = language.language() {
+        let mut parser = TS::Parser::new();
+        parser.set_language(&lang).unwrap();
+        let tree = parser.parse(header, None).unwrap();
+        let mut query_cursor = TS::QueryCursor::new();
+        for query in language.queries() {
+            if query.qtype != QType::Comments {
+                continue;
+            }
+            let mut matches =
+                query_cursor.matches(&query.query, tree.root_node(), header.as_bytes());
+            while let Some(m) = matches.next() {
+                comments.extend(m.captures.iter().map(|c| c.node.byte_range()));
+            }
+        }
+    } else if let Some(syntax) = language.syntax() {
+        comments = scanner::comments(header, syntax);
+    }
+    comments
+}
+
+/// Directories that usually contain code copied from other projects.
+const VENDORED_DIRS: [&str; 5] = ["vendor", "third_party", "third-party", "external", "deps"];
+
+/// Check if a file is in one of the `VENDORED_DIRS` or matches a vendored path. Only the
+/// directories below the scanned directory in `roots` that contains the file are checked, so
+/// a project that itself is checked out in e.g. `vendor` isn't vendored.
+fn is_vendored(filename: &str, roots: &[String], patterns: &[glob::Pattern]) -> bool {
+    let path = std::path::Path::new(filename);
+    let path = roots
+        .iter()
+        .filter_map(|r| path.strip_prefix(r).ok())
+        .min_by_key(|p| p.components().count())
+        .unwrap_or(path);
+    let in_vendored_dir = path.parent().is_some_and(|dir| {
+        dir.components()
+            .any(|c| VENDORED_DIRS.iter().any(|d| c.as_os_str() == *d))
+    });
+    in_vendored_dir || patterns.iter().any(|p| p.matches(filename))
+}
+
+/// Get the language of a file when the name isn't enough. A language given for the path in the
+/// options wins, where the last matching override comes from the nearest `.codlanguages`, then
+/// files that only match the catch-all language are checked for a shebang or modeline and `.h`
+/// files are checked for C++.
+fn pick_language<'a>(
+    languages: &'a [Box<dyn Language>],
+    l: &'a dyn Language,
+    filename: &str,
+    content: &str,
+    options: &Options,
+) -> &'a dyn Language {
+    let by_name = |name: &str| {
+        languages
+            .iter()
+            .find(|l| l.is_called(name))
+            .map(|l| l.as_ref())
+    };
+    if let Some(l) = options
+        .language_overrides
+        .iter()
+        .rev()
+        .find(|(p, _)| p.matches(filename))
+        .and_then(|(_, name)| by_name(name))
+    {
+        return l;
+    }
+    if l.name() == "Other" {
+        if let Some(l) = language_utils::content_language(content).and_then(|n| by_name(&n)) {
+            return l;
+        }
+    }
+    if filename.ends_with(".h") && l.name() == "C" {
+        if let Some(cpp) = by_name("Cpp").filter(|cpp| is_cpp_header(filename, content, l, *cpp)) {
+            return cpp;
+        }
+    }
+    l
+}
+
+/// Tokens that only are used in C++, a header with any of them is parsed as both C and C++.
+const CPP_TOKENS: [&str; 7] = [
+    "class ",
+    "namespace ",
+    "template",
+    "::",
+    "public:",
+    "private:",
+    "virtual ",
+];
+
+/// Check if a `.h` file is C++, either because it's next to a C++ source file with the same name
+/// or because it contains C++ tokens and has fewer parse errors as C++ than as C.
+fn is_cpp_header(filename: &str, content: &str, c: &dyn Language, cpp: &dyn Language) -> bool {
+    let path = std::path::Path::new(filename);
+    for (extension, is_cpp) in [("cpp", true), ("cc", true), ("cxx", true), ("c", false)] {
+        if path.with_extension(extension).is_file() {
+            return is_cpp;
+        }
+    }
+    if !CPP_TOKENS.iter().any(|t| content.contains(t)) {
+        return false;
+    }
+    let errors = |l: &dyn Language| {
+        let mut parser = TS::Parser::new();
+        parser.set_language(&l.language().unwrap()).unwrap();
+        let tree = parser.parse(content, None).unwrap();
+        parse_errors(&tree.root_node())
+    };
+    errors(cpp) < errors(c)
+}
+
+/// Count the error and missing nodes in a tree.
+fn parse_errors(node: &TS::Node) -> usize {
+    error_nodes(node).count()
+}
+
+/// Get the position of the first error or missing node in a tree.
+fn first_error(node: &TS::Node) -> Option<TS::Point> {
+    error_nodes(node).next().map(|n| n.start_position())
+}
+
+/// Iterate over the error and missing nodes in a tree. The tree is walked with a cursor, since
+/// recursion overflows the stack on deeply nested code, and only goes into nodes with errors.
+fn error_nodes<'a>(node: &TS::Node<'a>) -> impl Iterator<Item = TS::Node<'a>> {
+    let mut cursor = node.walk();
+    let mut depth = 0;
+    let mut done = false;
+    std::iter::from_fn(move || {
+        while !done {
+            let node = cursor.node();
+            let error = node.is_error() || node.is_missing();
+            if !error && node.has_error() && cursor.goto_first_child() {
+                depth += 1;
+                continue;
+            }
+            while depth > 0 && !cursor.goto_next_sibling() {
+                cursor.goto_parent();
+                depth -= 1;
+            }
+            done = depth == 0;
+            if error {
+                return Some(node);
+            }
+        }
+        None
+    })
+}
+
 fn parse_file(
     languages: &Vec<Box<dyn Language>>,
     language_map: &mut HashMap<String, Stats>,
     filename: &str,
+    options: &Options,
 ) {
     for l in languages {
         if l.matches_filename(filename) {
-            let Ok(content) = fs::read_to_string(filename) else {
+            let Some(content) = fs::read(filename).ok().and_then(|b| encoding::decode(&b)) else {
                 let name = "Binary";
                 if !language_map.contains_key(name) {
                     language_map.insert(name.to_string(), Stats::new(name));
@@ -132,39 +1184,104 @@ fn parse_file(
                 language_map.get_mut(name).unwrap().files += 1;
                 return;
             };
-            if !language_map.contains_key(l.name()) {
-                language_map.insert(l.name().to_string(), Stats::new(l.name()));
+            let l = pick_language(languages, l.as_ref(), filename, &content, options);
+            let name = if is_vendored(filename, &options.roots, &options.vendored) {
+                format!("{} (vendored)", l.name())
+            } else if is_generated(l, &content) {
+                if options.skip_generated {
+                    return;
+                }
+                format!("{} (generated)", l.name())
+            } else {
+                l.name().to_string()
+            };
+            if !language_map.contains_key(&name) {
+                language_map.insert(name.clone(), Stats::new(&name));
             }
-            language_map.get_mut(l.name()).unwrap().update(&content, l);
+            language_map
+                .get_mut(&name)
+                .unwrap()
+                .update(filename, &content, l, options);
             break;
         }
     }
 }
 
-fn parse_dir(file_list: &mut Vec<String>, ignore_list: &mut Vec<glob::Pattern>, dirname: &str) {
-    for entry in fs::read_dir(dirname).unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
+/// Read a file with one glob per line, like `.gitignore`, relative to the directory of the file.
+fn read_patterns(path: &std::path::Path) -> Vec<glob::Pattern> {
+    let mut patterns = vec![];
+    let content = fs::read_to_string(path).unwrap();
+    for line in content.lines() {
+        let l = line.trim();
+        if l.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        let mut s: String = path.parent().unwrap().to_str().unwrap().to_string() + "/" + l;
+        if !s.ends_with("*") {
+            s += "*";
+        }
+        patterns.push(glob::Pattern::new(s.trim_start_matches("./")).unwrap());
+    }
+    patterns
+}
+
+/// Read a file with lines like `*.h = Cpp` that set the language of the files matching a glob
+/// relative to the directory of the file.
+fn read_language_overrides(path: &std::path::Path) -> Vec<(glob::Pattern, String)> {
+    let mut overrides = vec![];
+    let content = fs::read_to_string(path).unwrap();
+    for line in content.lines() {
+        let l = line.trim();
+        if l.starts_with('#') || l.is_empty() {
+            continue;
+        }
+        let Some((pattern, language)) = l.split_once('=') else {
+            continue;
+        };
+        let s: String = path.parent().unwrap().to_str().unwrap().to_string() + "/" + pattern.trim();
+        overrides.push((
+            glob::Pattern::new(s.trim_start_matches("./")).unwrap(),
+            language.trim().to_string(),
+        ));
+    }
+    overrides
+}
+
+fn parse_dir(
+    file_list: &mut Vec<String>,
+    ignore_list: &mut Vec<glob::Pattern>,
+    vendored: &mut Vec<glob::Pattern>,
+    overrides: &mut Vec<(glob::Pattern, String)>,
+    dirname: &str,
+) {
+    let mut paths: Vec<_> = fs::read_dir(dirname)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    // Read the files of a directory before its subdirectories, so that the overrides of nested
+    // directories come after those of their parents.
+    paths.sort_by_key(|p| p.is_dir());
+    for path in paths {
         if path.file_name().unwrap() == ".gitignore" {
-            let content = fs::read_to_string(&path).unwrap();
-            for line in content.lines() {
-                let l = line.trim();
-                if l.starts_with('#') || line.len() == 0 {
-                    continue;
-                }
-                let mut s: String = path.parent().unwrap().to_str().unwrap().to_string() + "/" + l;
-                if !s.ends_with("*") {
-                    s += "*";
-                }
-                let pat = glob::Pattern::new(s.trim_start_matches("./")).unwrap();
-                ignore_list.push(pat);
-            }
+            ignore_list.extend(read_patterns(&path));
+        }
+        if path.file_name().unwrap() == ".codvendored" {
+            vendored.extend(read_patterns(&path));
+        }
+        if path.file_name().unwrap() == ".codlanguages" {
+            overrides.extend(read_language_overrides(&path));
         }
         if path.file_name().unwrap().to_str().unwrap().starts_with(".") {
             continue;
         }
         if path.is_dir() {
-            parse_dir(file_list, ignore_list, path.to_str().unwrap());
+            parse_dir(
+                file_list,
+                ignore_list,
+                vendored,
+                overrides,
+                path.to_str().unwrap(),
+            );
         } else {
             file_list.push(path.to_str().unwrap().trim_start_matches("./").to_string())
         }
@@ -187,6 +1304,18 @@ fn main() {
                 .help("Glob expression for files to ignore. Can be used multiple times.")
                 .action(clap::ArgAction::Append),
         )
+        .arg(
+            Arg::new("vendored")
+                .long("vendored")
+                .help("Glob expression for vendored files. Can be used multiple times.")
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("no-vendored")
+                .long("no-vendored")
+                .help("Ignore vendored files instead of counting them separately per language.")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("language")
                 .short('l')
@@ -200,6 +1329,95 @@ fn main() {
                 .help("Don't show summary, can be useful with -l.")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("column")
+                .short('c')
+                .long("column")
+                .help("Operation to show as a column in the summary, e.g. DocComments. Can be used multiple times. [default: Functions, Variables, Loops]")
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("functions")
+                .long("functions")
+                .help("List all functions with their size and complexity instead of the summary.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .help("What to sort the function list on.")
+                .value_parser(["lines", "params", "complexity", "cognitive", "nesting", "name", "path"])
+                .default_value("lines"),
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .help("Only list the N first functions.")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("max-params")
+                .long("max-params")
+                .help("Functions with more parameters than this are reported as having too many.")
+                .default_value("5")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("duplicates")
+                .long("duplicates")
+                .help("Find duplicated blocks of code.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("duplicate-tokens")
+                .long("duplicate-tokens")
+                .help("Minimum number of tokens in a duplicated block.")
+                .default_value("50")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("normalize-identifiers")
+                .long("normalize-identifiers")
+                .help("Find duplicated blocks that only differ in the names of identifiers.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("classes")
+                .long("classes")
+                .help("List all C++ classes and structs and Python classes instead of the summary.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dependencies")
+                .long("dependencies")
+                .help("Print the dependencies between files instead of the summary.")
+                .value_parser(["dot", "json", "summary"]),
+        )
+        .arg(
+            Arg::new("skip-generated")
+                .long("skip-generated")
+                .help("Ignore generated files instead of counting them separately per language.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("parse-errors")
+                .long("parse-errors")
+                .help("List the files with parse errors instead of the summary.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("todo-marker")
+                .long("todo-marker")
+                .help("Marker to look for in comments. Can be used multiple times.")
+                .default_values(["TODO", "FIXME", "HACK", "XXX", "SAFETY"])
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("list-todos")
+                .long("list-todos")
+                .help("List all markers found in comments instead of the summary.")
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("jobs")
                 .short('j')
@@ -217,8 +1435,14 @@ fn main() {
     let mut ignore: Vec<glob::Pattern> = matches
         .get_many::<String>("ignore")
         .unwrap_or_default()
-        .map(|s| glob::Pattern::new(&s).unwrap())
+        .map(|s| glob::Pattern::new(s).unwrap())
+        .collect();
+    let mut vendored: Vec<glob::Pattern> = matches
+        .get_many::<String>("vendored")
+        .unwrap_or_default()
+        .map(|s| glob::Pattern::new(s).unwrap())
         .collect();
+    let mut language_overrides = vec![];
     let wanted_langs: Vec<String> = matches
         .get_many::<String>("language")
         .unwrap_or_default()
@@ -229,10 +1453,41 @@ fn main() {
         .unwrap_or_default()
         .cloned()
         .collect::<Vec<String>>();
+    let column_args: Vec<String> = matches
+        .get_many::<String>("column")
+        .map(|c| c.cloned().collect())
+        .unwrap_or(vec![
+            "Functions".to_string(),
+            "Variables".to_string(),
+            "Loops".to_string(),
+        ]);
+    let list_functions = *matches.get_one::<bool>("functions").unwrap();
+    let sort = matches.get_one::<String>("sort").unwrap();
+    let top = matches.get_one::<usize>("top").copied();
+    let max_params: usize = *matches.get_one::<usize>("max-params").unwrap();
+    let duplicate_tokens: usize = *matches.get_one::<usize>("duplicate-tokens").unwrap();
+    let mut options = Options {
+        duplicates: *matches.get_one::<bool>("duplicates").unwrap(),
+        normalize_identifiers: *matches.get_one::<bool>("normalize-identifiers").unwrap(),
+        markers: matches
+            .get_many::<String>("todo-marker")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        dependencies: matches.get_one::<String>("dependencies").is_some(),
+        skip_generated: *matches.get_one::<bool>("skip-generated").unwrap(),
+        roots: vec![],
+        vendored: vec![],
+        language_overrides: vec![],
+        skip_vendored: *matches.get_one::<bool>("no-vendored").unwrap(),
+    };
+    let list_todos = *matches.get_one::<bool>("list-todos").unwrap();
+    let list_classes = *matches.get_one::<bool>("classes").unwrap();
+    let list_parse_errors = *matches.get_one::<bool>("parse-errors").unwrap();
     let show_summary = !*matches.get_one::<bool>("no-summary").unwrap();
     let jobs: usize = *matches.get_one::<usize>("jobs").unwrap();
 
-    let file_args = if file_args.len() > 0 {
+    let file_args = if !file_args.is_empty() {
         file_args
     } else {
         let mut files = vec![];
@@ -246,13 +1501,32 @@ fn main() {
         if std::path::Path::new(&f).is_file() {
             file_list.push(f);
         } else if std::path::Path::new(&f).is_dir() {
-            parse_dir(&mut file_list, &mut ignore, &f);
+            options.roots.push(f.trim_start_matches("./").to_string());
+            parse_dir(
+                &mut file_list,
+                &mut ignore,
+                &mut vendored,
+                &mut language_overrides,
+                &f,
+            );
+        }
+    }
+    for (pattern, name) in &language_overrides {
+        if !languages.iter().any(|l| l.is_called(name)) {
+            eprintln!(
+                "Warning: unknown language {} for {} in .codlanguages",
+                name, pattern
+            );
         }
     }
+    options.vendored = vendored;
+    options.language_overrides = language_overrides;
+    let options = Arc::new(options);
 
     file_list = file_list
         .iter()
         .filter(|f| ignore.iter().filter(|i| i.matches(f)).count() == 0)
+        .filter(|f| !(options.skip_vendored && is_vendored(f, &options.roots, &options.vendored)))
         .cloned()
         .collect();
 
@@ -262,65 +1536,133 @@ fn main() {
             .unwrap_or(std::ffi::OsStr::new(""))
             .to_str()
             .unwrap()
-            .to_string();
+            .to_string()
     });
 
     let list_sizes = file_list.len() / jobs;
     let (tx, rx) = mpsc::channel();
+    let (duplicates_tx, duplicates_rx) = mpsc::channel();
+    let mut token_txs = vec![];
     for i in 0..jobs - 1 {
         let langs = languages.clone();
+        let opts = options.clone();
         let fl = file_list[i * list_sizes..(i + 1) * list_sizes].to_vec();
         let thread_tx = tx.clone();
+        let thread_duplicates_tx = duplicates_tx.clone();
+        let (token_tx, token_rx) = mpsc::channel::<Arc<Vec<(String, Vec<FileTokens>)>>>();
+        token_txs.push(token_tx);
         thread::spawn(move || {
             let mut res = HashMap::<String, Stats>::new();
             for file in fl {
-                parse_file(&langs, &mut res, &file);
+                parse_file(&langs, &mut res, &file, &opts);
             }
             thread_tx.send(res).unwrap();
+            // With --duplicates the tokens of all threads come back to look for duplicates.
+            if let Ok(tokens) = token_rx.recv() {
+                let duplicates = duplication::find_in_share(&tokens, i, jobs, duplicate_tokens);
+                thread_duplicates_tx.send(duplicates).unwrap();
+            }
         });
     }
 
     for file in &file_list[(jobs - 1) * list_sizes..] {
-        parse_file(&languages, &mut language_map, &file);
+        parse_file(&languages, &mut language_map, file, &options);
     }
     for _ in 0..jobs - 1 {
-        for (k, v) in rx.recv().unwrap().iter() {
-            if language_map.contains_key(k) {
-                language_map.get_mut(k).unwrap().add(v);
+        for (k, v) in rx.recv().unwrap() {
+            if let Some(s) = language_map.get_mut(&k) {
+                s.add(v);
             } else {
-                language_map.insert(k.to_string(), v.clone());
+                language_map.insert(k, v);
             }
         }
     }
 
+    let mut duplicates = HashMap::new();
+    if options.duplicates {
+        let mut tokens: Vec<(String, Vec<FileTokens>)> = language_map
+            .values_mut()
+            .filter(|s| !s.tokens.is_empty())
+            .map(|s| (s.name.clone(), std::mem::take(&mut s.tokens)))
+            .collect();
+        tokens.sort_by(|a, b| a.0.cmp(&b.0));
+        let tokens = Arc::new(tokens);
+        for token_tx in &token_txs {
+            token_tx.send(Arc::clone(&tokens)).unwrap();
+        }
+        duplicates.extend(duplication::find_in_share(
+            &tokens,
+            jobs - 1,
+            jobs,
+            duplicate_tokens,
+        ));
+        for _ in 0..jobs - 1 {
+            duplicates.extend(duplicates_rx.recv().unwrap());
+        }
+    }
+    drop(token_txs);
+
     let mut stats = vec![];
     for v in language_map.values() {
         stats.push(v);
     }
+    let listed: Vec<&Stats> = stats
+        .iter()
+        .filter(|s| wanted_langs.is_empty() || wanted_langs.contains(&s.name.to_lowercase()))
+        .cloned()
+        .collect();
+    if list_functions {
+        print_functions(&listed, sort, top, max_params);
+        return;
+    }
+    if list_todos {
+        print_todos(&listed);
+        return;
+    }
+    if list_classes {
+        print_classes(&listed);
+        return;
+    }
+    if list_parse_errors {
+        print_parse_errors(&listed);
+        return;
+    }
+    if let Some(format) = matches.get_one::<String>("dependencies") {
+        print_dependencies(&listed, format);
+        return;
+    }
     if show_summary {
-        Stats::print_header();
+        let columns: Vec<String> = column_args
+            .iter()
+            .map(|c| {
+                stats
+                    .iter()
+                    .flat_map(|s| s.operations.keys())
+                    .map(|k| format!("{:?}", k))
+                    .find(|k| k.eq_ignore_ascii_case(c))
+                    .unwrap_or(c.clone())
+            })
+            .collect();
+        let name_width = name_width(stats.iter().map(|s| &s.name));
+        Stats::print_header(&columns, name_width);
 
         stats.sort_by_key(|s| s.name.clone());
         let mut total = Stats::new("Total");
-        total.operations.insert(QType::Functions, 0);
-        total.operations.insert(QType::Variables, 0);
-        total.operations.insert(QType::Loops, 0);
         for s in stats.iter() {
-            s.print();
-            total.files += s.files;
-            total.total_lines += s.total_lines;
-            total.blank_lines += s.blank_lines;
-            *total.operations.get_mut(&QType::Functions).unwrap() +=
-                s.operations.get(&QType::Functions).unwrap_or(&0);
-            *total.operations.get_mut(&QType::Variables).unwrap() +=
-                s.operations.get(&QType::Variables).unwrap_or(&0);
-            *total.operations.get_mut(&QType::Loops).unwrap() +=
-                s.operations.get(&QType::Loops).unwrap_or(&0);
-        }
-        println!("{:-<width$}", "", width = 7 * 15);
-        total.print();
+            s.print(&columns, name_width);
+            total.merge(s);
+        }
+        println!(
+            "{:-<width$}",
+            "",
+            width = name_width + (5 + columns.len()) * 15
+        );
+        total.print(&columns, name_width);
         println!();
     }
+    if options.duplicates {
+        print_duplicates(&stats, &duplicates);
+    }
     let mut other_endings = vec![];
     for file in file_list {
         for l in languages.iter() {
@@ -339,7 +1681,7 @@ fn main() {
     }
     for s in stats.iter() {
         if wanted_langs.contains(&s.name.to_lowercase()) {
-            s.print_detailed();
+            s.print_detailed(max_params, &options.markers);
             if s.name.to_lowercase() == "other" {
                 println!("Other file endings: ");
                 for e in &other_endings {
@@ -351,6 +1693,7 @@ fn main() {
     }
 }
 
+#[cfg(test)]
 fn print_nodes(filename: &str, lang: TS::Language) {
     let content = fs::read_to_string(filename).unwrap();
     let mut parser = TS::Parser::new();
@@ -364,6 +1707,7 @@ fn print_nodes(filename: &str, lang: TS::Language) {
     }
 }
 
+#[cfg(test)]
 fn next_node<'a>(cursor: &mut TS::TreeCursor<'a>) -> Option<TS::Node<'a>> {
     if cursor.goto_first_child() {
         return Some(cursor.node());
@@ -387,14 +1731,26 @@ fn next_node<'a>(cursor: &mut TS::TreeCursor<'a>) -> Option<TS::Node<'a>> {
 mod test {
     use super::*;
 
-    fn lang_arc() -> Arc<Vec<Box<dyn Language>>> {
-        Arc::new(languages::languages())
+    /// Parse `paths` with `options`, the stats are keyed by the language or bucket name.
+    fn parse_all(paths: &[&str], options: &Options) -> HashMap<String, Stats> {
+        let languages = Arc::new(languages::languages());
+        let mut language_map = HashMap::new();
+        for path in paths {
+            parse_file(&languages, &mut language_map, path, options);
+        }
+        language_map
+    }
+
+    /// Parse a single file with the default options.
+    fn parse(path: &str) -> Stats {
+        let language_map = parse_all(&[path], &Options::default());
+        assert_eq!(language_map.len(), 1);
+        language_map.into_values().next().unwrap()
     }
+
     #[test]
     fn read_rust() {
-        let mut language_map: HashMap<String, Stats> = HashMap::new();
-        let mut languages = lang_arc();
-        parse_file(&mut languages, &mut language_map, "test_files/test.rs");
+        let language_map = parse_all(&["test_files/test.rs"], &Options::default());
         assert!(language_map.contains_key("Rust"));
         let rust = language_map.get("Rust").unwrap();
         assert_eq!(rust.files, 1);
@@ -407,9 +1763,7 @@ mod test {
 
     #[test]
     fn read_cpp() {
-        let mut language_map: HashMap<String, Stats> = HashMap::new();
-        let mut languages = lang_arc();
-        parse_file(&mut languages, &mut language_map, "test_files/test.cpp");
+        let language_map = parse_all(&["test_files/test.cpp"], &Options::default());
         assert!(language_map.contains_key("Cpp"));
         let cpp = language_map.get("Cpp").unwrap();
         assert_eq!(cpp.files, 1);
@@ -424,9 +1778,7 @@ mod test {
 
     #[test]
     fn read_c() {
-        let mut language_map: HashMap<String, Stats> = HashMap::new();
-        let mut languages = lang_arc();
-        parse_file(&mut languages, &mut language_map, "test_files/test.c");
+        let language_map = parse_all(&["test_files/test.c"], &Options::default());
         assert!(language_map.contains_key("C"));
         let c = language_map.get("C").unwrap();
         assert_eq!(c.files, 1);
@@ -435,14 +1787,16 @@ mod test {
         assert_eq!(c.operations.get(&QType::Functions).unwrap(), &2);
         assert_eq!(c.operations.get(&QType::Variables).unwrap(), &4);
         assert_eq!(c.operations.get(&QType::Loops).unwrap(), &3);
+        let names: Vec<&str> = c.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["add", "main"]);
+        let parameters: Vec<usize> = c.functions.iter().map(|f| f.parameters).collect();
+        assert_eq!(parameters, vec![2, 0]);
     }
 
     #[test]
     fn read_zig() {
         print_nodes("test_files/test.zig", tree_sitter_zig::LANGUAGE.into());
-        let mut language_map: HashMap<String, Stats> = HashMap::new();
-        let mut languages = lang_arc();
-        parse_file(&mut languages, &mut language_map, "test_files/test.zig");
+        let language_map = parse_all(&["test_files/test.zig"], &Options::default());
         assert!(language_map.contains_key("Zig"));
         let zig = language_map.get("Zig").unwrap();
         assert_eq!(zig.files, 1);
@@ -452,4 +1806,450 @@ mod test {
         assert_eq!(zig.operations.get(&QType::Variables).unwrap(), &9);
         assert_eq!(zig.operations.get(&QType::Loops).unwrap(), &4);
     }
+
+    #[test]
+    fn cyclomatic_complexity() {
+        let rust = parse("test_files/complexity.rs");
+        let names: Vec<&str> = rust.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["simple", "branches", "checked"]);
+        let lines: Vec<usize> = rust.functions.iter().map(|f| f.lines()).collect();
+        assert_eq!(lines, vec![3, 19, 6]);
+        let parameters: Vec<usize> = rust.functions.iter().map(|f| f.parameters).collect();
+        assert_eq!(parameters, vec![0, 3, 1]);
+        let complexity: Vec<usize> = rust.functions.iter().map(|f| f.complexity).collect();
+        assert_eq!(complexity, vec![1, 11, 2]);
+        let cognitive: Vec<usize> = rust.functions.iter().map(|f| f.cognitive).collect();
+        assert_eq!(cognitive, vec![0, 15, 1]);
+        let nesting: Vec<usize> = rust.functions.iter().map(|f| f.nesting).collect();
+        assert_eq!(nesting, vec![0, 3, 1]);
+        assert_eq!(rust.functions[0].halstead.length(), 7);
+        assert_eq!(rust.file_metrics.len(), 1);
+        assert!(rust.functions[0].maintainability > rust.functions[1].maintainability);
+    }
+
+    #[test]
+    fn block_nesting() {
+        let rust = parse("test_files/nesting.rs");
+        let nesting: Vec<usize> = rust.functions.iter().map(|f| f.nesting).collect();
+        assert_eq!(nesting, vec![3, 1]);
+        let cognitive: Vec<usize> = rust.functions.iter().map(|f| f.cognitive).collect();
+        assert_eq!(cognitive, vec![1, 3]);
+    }
+
+    #[test]
+    fn catch_all_arms() {
+        let zig = parse("test_files/switch.zig");
+        let complexity: Vec<usize> = zig.functions.iter().map(|f| f.complexity).collect();
+        assert_eq!(complexity, vec![3]);
+
+        let python = parse("test_files/match.py");
+        let complexity: Vec<usize> = python.functions.iter().map(|f| f.complexity).collect();
+        assert_eq!(complexity, vec![4]);
+    }
+
+    /// Parse a function returning the expression `inner` in `depth` levels of parentheses.
+    fn deeply_nested(depth: usize, inner: &str) -> TS::Tree {
+        let content = format!(
+            "fn f() -> i32 {{ {}{}{} }}",
+            "(".repeat(depth),
+            inner,
+            ")".repeat(depth)
+        );
+        let mut parser = TS::Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        parser.parse(&content, None).unwrap()
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let tree = deeply_nested(100_000, "1");
+        let function = tree.root_node().child(0).unwrap();
+        assert_eq!(complexity::cyclomatic(&[function], &[]), vec![1]);
+        assert_eq!(
+            complexity::cognitive(&[function], &HashMap::new()),
+            vec![(0, 0)]
+        );
+    }
+
+    #[test]
+    fn parameter_counts() {
+        let cpp = parse("test_files/parameters.cpp");
+        let names: Vec<&str> = cpp.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second", "none", "many"]);
+        let parameters: Vec<usize> = cpp.functions.iter().map(|f| f.parameters).collect();
+        assert_eq!(parameters, vec![2, 1, 0, 6]);
+    }
+
+    #[test]
+    fn merge_function_distributions() {
+        let files = ["test_files/complexity.rs", "test_files/test.rs"];
+        let sequential = parse_all(&files, &Options::default());
+        let mut merged = Stats::new("Rust");
+        for f in files {
+            merged.add(parse(f));
+        }
+
+        let lengths = |s: &Stats| Distribution::new(s.functions.iter().map(|f| f.lines()));
+        let sequential = lengths(sequential.get("Rust").unwrap());
+        let merged = lengths(&merged);
+        assert_eq!(merged.median(), sequential.median());
+        assert_eq!(merged.percentile(90), sequential.percentile(90));
+        assert_eq!(
+            merged.histogram(&distribution::LENGTH_BUCKETS),
+            vec![3, 2, 0, 0, 0]
+        );
+        assert_eq!(
+            merged.histogram(&distribution::LENGTH_BUCKETS),
+            sequential.histogram(&distribution::LENGTH_BUCKETS)
+        );
+    }
+
+    #[test]
+    fn comment_lines_rust() {
+        let rust = parse("test_files/comments.rs");
+        assert_eq!(rust.total_lines, 15);
+        assert_eq!(rust.blank_lines, 3);
+        assert_eq!(rust.comment_lines, 5);
+        assert_eq!(rust.code_lines, 7);
+        assert_eq!(rust.operations.get(&QType::DocComments).unwrap(), &2);
+    }
+
+    #[test]
+    fn unsafe_rust() {
+        let rust = parse("test_files/unsafe.rs");
+        let count = |q: QType| *rust.operations.get(&q).unwrap();
+        assert_eq!(count(QType::UnsafeBlocks), 2);
+        assert_eq!(count(QType::UnsafeLines), 5);
+        assert_eq!(count(QType::UnsafeFunctions), 2);
+        assert_eq!(count(QType::UnsafeImpls), 1);
+        assert_eq!(count(QType::UnsafeTraits), 1);
+        assert_eq!(count(QType::ExternBlocks), 1);
+        assert_eq!(count(QType::RawPointerDerefs), 2);
+    }
+
+    #[test]
+    fn test_code() {
+        let language_map = parse_all(
+            &[
+                "test_files/tests.rs",
+                "test_files/tests.zig",
+                "test_files/tests.cpp",
+            ],
+            &Options::default(),
+        );
+        let tests = |name: &str| {
+            let s = language_map.get(name).unwrap();
+            let functions = *s.operations.get(&QType::TestFunctions).unwrap();
+            (functions, s.test_lines, s.code_lines - s.test_lines)
+        };
+        assert_eq!(tests("Rust"), (2, 17, 3));
+        assert_eq!(tests("Zig"), (1, 3, 3));
+        assert_eq!(tests("Cpp"), (2, 6, 1));
+        assert!(is_test_path("src/tests/a.c"));
+        let helpers = parse("test_files/tests/common.rs");
+        assert_eq!(helpers.operations.get(&QType::TestFunctions), Some(&3));
+        assert_eq!(helpers.operations.get(&QType::Functions), Some(&3));
+        assert_eq!(helpers.test_lines, helpers.code_lines);
+        assert!(!is_test_path("test_files/tests.c"));
+    }
+
+    #[test]
+    fn rust_items() {
+        let rust = parse("test_files/items.rs");
+        let count = |q: QType| *rust.operations.get(&q).unwrap();
+        assert_eq!(count(QType::Structs), 2);
+        assert_eq!(count(QType::Enums), 1);
+        assert_eq!(count(QType::Unions), 1);
+        assert_eq!(count(QType::Traits), 1);
+        assert_eq!(count(QType::InherentImpls), 1);
+        assert_eq!(count(QType::TraitImpls), 2);
+        assert_eq!(count(QType::TypeAliases), 1);
+        assert_eq!(count(QType::Macros), 1);
+        assert_eq!(count(QType::Modules), 2);
+        assert_eq!(count(QType::Uses), 2);
+    }
+
+    #[test]
+    fn cpp_classes() {
+        let cpp = parse("test_files/classes.cpp");
+        let count = |q: QType| *cpp.operations.get(&q).unwrap();
+        assert_eq!(count(QType::Classes), 4);
+        assert_eq!(count(QType::Methods), 7);
+        assert_eq!(count(QType::VirtualFunctions), 3);
+        assert_eq!(count(QType::PureVirtualFunctions), 1);
+        assert_eq!(count(QType::Overrides), 2);
+        assert_eq!(count(QType::BaseClasses), 3);
+        assert_eq!(count(QType::Namespaces), 2);
+        let names: Vec<&str> = cpp
+            .classes
+            .iter()
+            .map(|c| c.qualified_name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "shapes::Shape",
+                "shapes::Point",
+                "shapes::Rect",
+                "shapes::Square"
+            ]
+        );
+        let methods: Vec<usize> = cpp.classes.iter().map(|c| c.methods).collect();
+        assert_eq!(methods, vec![4, 0, 2, 1]);
+        let classes: Vec<&Class> = cpp.classes.iter().collect();
+        assert_eq!(classes::inheritance_depths(&classes), vec![0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn zig_items() {
+        let zig = parse("test_files/zig_items.zig");
+        let count = |q: QType| *zig.operations.get(&q).unwrap();
+        assert_eq!(count(QType::ComptimeBlocks), 1);
+        assert_eq!(count(QType::ComptimeParameters), 1);
+        assert_eq!(count(QType::TestFunctions), 1);
+        assert_eq!(count(QType::Defers), 1);
+        assert_eq!(count(QType::ErrDefers), 1);
+        assert_eq!(count(QType::ErrorSets), 1);
+        assert_eq!(count(QType::Tries), 3);
+        assert_eq!(count(QType::Imports), 1);
+        assert_eq!(count(QType::CImports), 1);
+        assert_eq!(count(QType::Structs), 1);
+        assert_eq!(count(QType::Enums), 1);
+        assert_eq!(count(QType::Unions), 1);
+    }
+
+    #[test]
+    fn python_items() {
+        let python = parse("test_files/python_items.py");
+        let count = |q: QType| *python.operations.get(&q).unwrap();
+        assert_eq!(count(QType::Functions), 5);
+        assert_eq!(count(QType::Classes), 2);
+        assert_eq!(count(QType::Methods), 3);
+        assert_eq!(count(QType::BaseClasses), 1);
+        assert_eq!(count(QType::Loops), 2);
+        assert_eq!(count(QType::Comprehensions), 2);
+        assert_eq!(count(QType::Imports), 2);
+        assert_eq!(count(QType::Decorators), 2);
+        assert_eq!(count(QType::Variables), 7);
+        assert_eq!(count(QType::ModuleVariables), 2);
+        assert_eq!(count(QType::LocalVariables), 4);
+        let names: Vec<&str> = python.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["area", "__init__", "area", "total", "<anonymous>"]
+        );
+        let bases: Vec<&[String]> = python.classes.iter().map(|c| c.bases.as_slice()).collect();
+        assert_eq!(bases, vec![&[][..], &["Shape".to_string()][..]]);
+    }
+
+    #[test]
+    fn rust_imports() {
+        let options = Options {
+            dependencies: true,
+            ..Default::default()
+        };
+        let language_map = parse_all(&["test_files/items.rs"], &options);
+        let rust = language_map.get("Rust").unwrap();
+        assert_eq!(rust.dependencies.len(), 1);
+        let imports: Vec<(ImportKind, &str)> = rust.dependencies[0]
+            .imports
+            .iter()
+            .map(|i| (i.kind, i.name.as_str()))
+            .collect();
+        assert_eq!(
+            imports,
+            vec![
+                (ImportKind::Module, "inner"),
+                (ImportKind::Use, "std::fmt"),
+                (ImportKind::Use, "std::collections"),
+            ]
+        );
+    }
+
+    #[test]
+    fn public_items() {
+        let language_map = parse_all(
+            &[
+                "test_files/api.rs",
+                "test_files/api.h",
+                "test_files/api.zig",
+            ],
+            &Options::default(),
+        );
+        let count = |name: &str, q: QType| *language_map[name].operations.get(&q).unwrap();
+        assert_eq!(count("Rust", QType::PublicItems), 5);
+        assert_eq!(count("Rust", QType::RestrictedItems), 2);
+        assert_eq!(count("Rust", QType::UndocumentedItems), 2);
+        assert_eq!(count("C", QType::PublicItems), 3);
+        assert_eq!(count("C", QType::UndocumentedItems), 2);
+        assert_eq!(count("Zig", QType::PublicItems), 2);
+        assert_eq!(count("Zig", QType::UndocumentedItems), 1);
+    }
+
+    #[test]
+    fn generated_files() {
+        let language_map = parse_all(&["test_files/generated.rs"], &Options::default());
+        assert!(!language_map.contains_key("Rust"));
+        assert_eq!(language_map["Rust (generated)"].code_lines, 1);
+        assert_eq!(language_map["Rust (generated)"].comment_lines, 1);
+
+        let options = Options {
+            skip_generated: true,
+            ..Default::default()
+        };
+        assert!(parse_all(&["test_files/generated.rs"], &options).is_empty());
+        let languages = languages::languages();
+        let language = |name| {
+            languages
+                .iter()
+                .find(|l| l.name() == name)
+                .unwrap()
+                .as_ref()
+        };
+        assert!(is_generated(
+            language("Go"),
+            "// Code generated by stringer. DO NOT EDIT.\n"
+        ));
+        assert!(!is_generated(language("Rust"), "fn main() {}\n"));
+        assert!(!is_generated(
+            language("Rust"),
+            "const WARNING: &str = \"DO NOT EDIT\";\n"
+        ));
+    }
+
+    #[test]
+    fn vendored_files() {
+        assert!(is_vendored("third_party/zlib/inflate.c", &[], &[]));
+        assert!(is_vendored("src/vendor/lib.rs", &[], &[]));
+        assert!(!is_vendored("src/vendor.rs", &[], &[]));
+        // Only directories below the scanned one count.
+        let roots = ["test_files/third_party".to_string()];
+        assert!(!is_vendored(
+            "test_files/third_party/inflate.c",
+            &roots,
+            &[]
+        ));
+        let roots = ["test_files".to_string()];
+        assert!(is_vendored("test_files/third_party/inflate.c", &roots, &[]));
+
+        let patterns = [glob::Pattern::new("libs/imported*").unwrap()];
+        assert!(is_vendored("libs/imported/a.c", &[], &patterns));
+        assert!(!is_vendored("libs/own/a.c", &[], &patterns));
+
+        let options = Options {
+            vendored: vec![glob::Pattern::new("test_files/test*").unwrap()],
+            ..Default::default()
+        };
+        let language_map = parse_all(&["test_files/test.c"], &options);
+        assert_eq!(language_map["C (vendored)"].files, 1);
+    }
+
+    #[test]
+    fn detect_from_content() {
+        let python = parse("test_files/deploy");
+        assert_eq!(python.files, 1);
+        assert_eq!(python.comment_lines, 2);
+    }
+
+    #[test]
+    fn cpp_headers() {
+        let language_map = parse_all(
+            &["test_files/shapes.h", "test_files/api.h"],
+            &Options::default(),
+        );
+        assert_eq!(language_map["Cpp"].files, 1);
+        assert_eq!(language_map["C"].files, 1);
+
+        let options = Options {
+            language_overrides: vec![(
+                glob::Pattern::new("test_files/*.h").unwrap(),
+                "cpp".to_string(),
+            )],
+            ..Default::default()
+        };
+        assert_eq!(parse_all(&["test_files/api.h"], &options)["Cpp"].files, 1);
+
+        let options = Options {
+            language_overrides: vec![
+                (glob::Pattern::new("*.h").unwrap(), "cpp".to_string()),
+                (
+                    glob::Pattern::new("test_files/*.h").unwrap(),
+                    "c".to_string(),
+                ),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(parse_all(&["test_files/shapes.h"], &options)["C"].files, 1);
+    }
+
+    #[test]
+    fn legacy_encodings() {
+        let c = parse("test_files/latin1.c");
+        assert_eq!(c.comment_lines, 1);
+        assert_eq!(c.code_lines, 1);
+        assert_eq!(c.operations.get(&QType::Variables).unwrap(), &1);
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let language_map = parse_all(
+            &["test_files/broken.c", "test_files/test.c"],
+            &Options::default(),
+        );
+        let errors = &language_map["C"].parse_errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "test_files/broken.c");
+        assert_eq!((errors[0].line, errors[0].column), (3, 13));
+
+        let tree = deeply_nested(100_000, "1 +");
+        assert_eq!(parse_errors(&tree.root_node()), 1);
+        assert!(first_error(&tree.root_node()).is_some());
+    }
+
+    #[test]
+    fn comment_lines_c() {
+        let c = parse("test_files/comments.c");
+        assert_eq!(c.total_lines, 10);
+        assert_eq!(c.blank_lines, 1);
+        assert_eq!(c.comment_lines, 5);
+        assert_eq!(c.code_lines, 4);
+        assert_eq!(c.operations.get(&QType::DocComments).unwrap(), &4);
+    }
+
+    #[test]
+    fn comment_lines_zig() {
+        let zig = parse("test_files/comments.zig");
+        assert_eq!(zig.comment_lines, 4);
+        assert_eq!(zig.code_lines, 3);
+        assert_eq!(zig.operations.get(&QType::DocComments).unwrap(), &2);
+    }
+
+    #[test]
+    fn comment_lines_python() {
+        let python = parse("test_files/comments.py");
+        assert_eq!(python.total_lines, 11);
+        assert_eq!(python.blank_lines, 3);
+        assert_eq!(python.comment_lines, 2);
+        assert_eq!(python.code_lines, 6);
+    }
+
+    #[test]
+    fn todo_markers() {
+        let options = Options {
+            markers: vec!["TODO".to_string(), "FIXME".to_string()],
+            ..Default::default()
+        };
+        let language_map = parse_all(&["test_files/todos.py"], &options);
+        let python = language_map.get("Python").unwrap();
+        let todos: Vec<(usize, &str)> = python
+            .todos
+            .iter()
+            .map(|t| (t.line, t.marker.as_str()))
+            .collect();
+        assert_eq!(todos, vec![(1, "TODO"), (7, "FIXME")]);
+        assert_eq!(python.todos[0].author.as_deref(), Some("anna"));
+        assert_eq!(python.todos[1].text, "FIXME handle errors");
+    }
 }
@@ -0,0 +1,165 @@
+use crate::language_utils::Syntax;
+use std::ops::Range;
+
+/// Find the byte ranges of all comments in `content` using the lexical rules in `syntax`.
+/// String literals are skipped so comment markers inside them are not mistaken for comments.
+pub fn comments(content: &str, syntax: &Syntax) -> Vec<Range<usize>> {
+    let bytes = content.as_bytes();
+    let mut comments = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if let Some((open, close)) = syntax
+            .block_comments
+            .iter()
+            .find(|(open, _)| rest.starts_with(open.as_bytes()))
+        {
+            let end = block_comment_end(bytes, i + open.len(), open, close, syntax.nested);
+            comments.push(i..end);
+            i = end;
+        } else if is_line_comment(bytes, i, syntax) {
+            let end = rest
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(bytes.len(), |p| i + p);
+            comments.push(i..end);
+            i = end;
+        } else if let Some((open, close, escape, multiline)) = syntax
+            .strings
+            .iter()
+            .find(|(open, _, _, _)| rest.starts_with(open.as_bytes()))
+        {
+            i = string_end(bytes, i + open.len(), close, *escape, *multiline);
+        } else {
+            i += 1;
+        }
+    }
+    comments
+}
+
+fn is_line_comment(bytes: &[u8], i: usize, syntax: &Syntax) -> bool {
+    let rest = &bytes[i..];
+    let line_start = || {
+        bytes[..i]
+            .iter()
+            .rev()
+            .take_while(|&&b| b != b'\n')
+            .all(u8::is_ascii_whitespace)
+    };
+    let spaced = !syntax.spaced_line_comments || i == 0 || bytes[i - 1].is_ascii_whitespace();
+    (spaced
+        && syntax
+            .line_comments
+            .iter()
+            .any(|m| rest.starts_with(m.as_bytes())))
+        || (syntax
+            .line_start_comments
+            .iter()
+            .any(|m| rest.starts_with(m.as_bytes()))
+            && line_start())
+}
+
+fn block_comment_end(bytes: &[u8], mut i: usize, open: &str, close: &str, nested: bool) -> usize {
+    let mut depth = 1;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(close.as_bytes()) {
+            i += close.len();
+            depth -= 1;
+            if depth == 0 {
+                return i;
+            }
+        } else if nested && rest.starts_with(open.as_bytes()) {
+            i += open.len();
+            depth += 1;
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn string_end(bytes: &[u8], mut i: usize, close: &str, escape: bool, multiline: bool) -> usize {
+    while i < bytes.len() {
+        if !multiline && bytes[i] == b'\n' {
+            return i;
+        } else if escape && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i..].starts_with(close.as_bytes()) {
+            return i + close.len();
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SYNTAX: Syntax = Syntax {
+        line_comments: &["//"],
+        spaced_line_comments: false,
+        line_start_comments: &[],
+        block_comments: &[("/*", "*/")],
+        nested: true,
+        strings: &[("\"", "\"", true, false)],
+    };
+
+    #[test]
+    fn skips_strings() {
+        let content = r#"let s = "// \" /*"; // comment"#;
+        assert_eq!(comments(content, &SYNTAX), vec![20..30]);
+    }
+
+    #[test]
+    fn nested_block_comments() {
+        let content = "/* a /* b */ c */ d";
+        assert_eq!(comments(content, &SYNTAX), vec![0..17]);
+        let flat = Syntax {
+            nested: false,
+            ..SYNTAX
+        };
+        assert_eq!(comments(content, &flat), vec![0..12]);
+    }
+
+    #[test]
+    fn comment_positions() {
+        let shell = Syntax {
+            line_comments: &["#"],
+            spaced_line_comments: true,
+            line_start_comments: &[],
+            block_comments: &[],
+            nested: false,
+            strings: &[],
+        };
+        let content = "echo $# ${#x} # count
+# line";
+        assert_eq!(comments(content, &shell), vec![14..21, 22..28]);
+        let vim = Syntax {
+            line_comments: &[],
+            spaced_line_comments: false,
+            line_start_comments: &["\""],
+            block_comments: &[],
+            nested: false,
+            strings: &[("\"", "\"", true, false)],
+        };
+        let content = "  \" comment\necho \"text\"";
+        assert_eq!(comments(content, &vim), vec![2..11]);
+    }
+
+    #[test]
+    fn unterminated_strings() {
+        let yaml = Syntax {
+            line_comments: &["#"],
+            spaced_line_comments: false,
+            line_start_comments: &[],
+            block_comments: &[],
+            nested: false,
+            strings: &[("\"", "\"", true, false), ("'", "'", false, false)],
+        };
+        let content = "# name\nname: it's broken\n# comment\n";
+        assert_eq!(comments(content, &yaml), vec![0..6, 25..34]);
+    }
+}
@@ -0,0 +1,93 @@
+use std::ops::Range;
+
+/// A marker like TODO or FIXME found in a comment.
+#[derive(Clone)]
+pub struct Todo {
+    pub path: String,
+    pub line: usize,
+    pub marker: String,
+    /// The name in e.g. `TODO(name)`.
+    pub author: Option<String>,
+    /// The comment text starting at the marker.
+    pub text: String,
+}
+
+/// Find `markers` in the `comments` of a file. Markers only count as whole words and at most
+/// one marker is reported per line. Line numbers are counted as the comments are walked, which
+/// is fastest when the comments are in the order they appear in the file.
+pub fn find_todos(
+    path: &str,
+    content: &str,
+    comments: &[Range<usize>],
+    markers: &[String],
+) -> Vec<Todo> {
+    let mut todos = vec![];
+    if markers.is_empty() {
+        return todos;
+    }
+    let mut line = 1;
+    let mut position = 0;
+    for comment in comments {
+        if comment.start < position {
+            line = 1;
+            position = 0;
+        }
+        line += content[position..comment.start].matches('\n').count();
+        position = comment.start;
+        let first_line = line;
+        for (i, text) in content[comment.clone()].split('\n').enumerate() {
+            if let Some((start, marker)) = find_marker(text, markers) {
+                let rest = &text[start + marker.len()..];
+                let author = rest
+                    .strip_prefix('(')
+                    .and_then(|r| r.split_once(')'))
+                    .map(|(name, _)| name.trim().to_string());
+                todos.push(Todo {
+                    path: path.to_string(),
+                    line: first_line + i,
+                    marker: marker.to_string(),
+                    author,
+                    text: text[start..].trim_end_matches("*/").trim().to_string(),
+                });
+            }
+        }
+    }
+    todos
+}
+
+/// Find the first marker in `text` that isn't part of a longer word.
+fn find_marker<'a>(text: &str, markers: &'a [String]) -> Option<(usize, &'a str)> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    markers
+        .iter()
+        .flat_map(|m| {
+            text.match_indices(m.as_str())
+                .map(move |(i, _)| (i, m.as_str()))
+        })
+        .filter(|(i, m)| {
+            !is_word(text[..*i].chars().last()) && !is_word(text[i + m.len()..].chars().next())
+        })
+        .min_by_key(|(i, _)| *i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn find_markers() {
+        let content = "x(); // TODO(anna): fix\n/* TODOS\n * FIXME: later */\n// HACK\n";
+        let comments = vec![5..23, 24..51, 52..59];
+        let markers: Vec<String> = ["TODO", "FIXME", "HACK"].map(String::from).to_vec();
+        let todos = find_todos("a.c", content, &comments, &markers);
+        assert_eq!(todos.len(), 3);
+        assert_eq!(todos[0].line, 1);
+        assert_eq!(todos[0].author.as_deref(), Some("anna"));
+        assert_eq!(todos[0].text, "TODO(anna): fix");
+        assert_eq!(todos[1].line, 3);
+        assert_eq!(todos[1].marker, "FIXME");
+        assert_eq!(todos[1].text, "FIXME: later");
+        assert_eq!(todos[2].line, 4);
+        assert_eq!(todos[2].author, None);
+    }
+}
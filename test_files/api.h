@@ -0,0 +1,8 @@
+#ifndef A_H
+#define A_H
+/** Adds. */
+int add(int a, int b);
+static int helper(void) { return 1; }
+struct point { int x; };
+typedef struct point point_t;
+#endif
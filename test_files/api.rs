@@ -0,0 +1,26 @@
+//! A small API.
+
+/// A point.
+#[derive(Clone)]
+pub struct Point {
+    /// The x coordinate.
+    pub x: i32,
+    pub y: i32,
+    z: i32,
+}
+
+impl Point {
+    // Not a doc comment.
+    pub fn new() -> Self {
+        Point { x: 0, y: 0, z: 0 }
+    }
+
+    pub(crate) fn reset(&mut self) {}
+}
+
+#[doc = "The origin."]
+pub const ORIGIN: Point = Point { x: 0, y: 0, z: 0 };
+
+pub(super) mod inner {}
+
+pub use std::fmt;
@@ -0,0 +1,6 @@
+/// Adds.
+pub fn add(a: u32) u32 {
+    return a;
+}
+pub const x = 1;
+const y = 2;
@@ -0,0 +1,30 @@
+namespace shapes {
+namespace detail {}
+
+class Shape {
+public:
+    Shape();
+    virtual ~Shape() = default;
+    virtual double area() const = 0;
+    virtual const char *name() const { return "shape"; }
+};
+
+struct Point {
+    int x;
+    int y;
+    void (*moved)(int dx, int dy);
+};
+
+class Rect : public Shape {
+public:
+    double area() const override { return w * h; }
+    const char *name() const override;
+
+private:
+    double w, h;
+};
+
+class Square final : public Rect, private Point {
+    int side() const { return 1; }
+};
+} // namespace shapes
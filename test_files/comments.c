@@ -0,0 +1,10 @@
+/**
+ * Doxygen documentation.
+ */
+int add(int a, int b) {
+  // Plain comment.
+  return a + b; /* Trailing comment. */
+}
+
+/// Doxygen line.
+int main() { return add(1, 2); }
@@ -0,0 +1,11 @@
+#!/usr/bin/env python3
+# A comment.
+
+URL = "http://example.com/#anchor"  # Trailing comment.
+
+
+def main():
+    text = """
+    # Not a comment.
+    """
+    return text  # Another trailing comment.
@@ -0,0 +1,15 @@
+//! Module documentation.
+
+/// Adds one.
+fn add_one(i: usize) -> usize {
+    // Plain comment.
+    i + 1 // Trailing comment.
+}
+
+/* Block comment
+   spanning two lines. */
+fn main() {
+    let s = "// not a comment";
+
+    println!("{} {}", s, add_one(1)); /* trailing block */
+}
@@ -0,0 +1,9 @@
+//! Module documentation.
+
+/// Adds one.
+fn addOne(i: usize) usize {
+    // Plain comment.
+    return i + 1; // Trailing comment.
+}
+
+//// Not documentation.
@@ -0,0 +1,30 @@
+fn simple() -> usize {
+    1
+}
+
+fn branches(a: usize, b: bool, c: bool) -> Option<usize> {
+    if b && c || a > 1 {
+        for i in 0..a {
+            if i == 2 {
+                return Some(i);
+            }
+        }
+    } else if let Some(x) = checked(a) {
+        return Some(x);
+    } else {
+        let f = |x: usize| if x > 1 { x } else { 0 };
+        return Some(f(a));
+    }
+    match a {
+        0 => None,
+        1 => Some(checked(a)?),
+        _ => Some(a),
+    }
+}
+
+fn checked(a: usize) -> Option<usize> {
+    while a > 10 {
+        return None;
+    }
+    Some(a)
+}
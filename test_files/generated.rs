@@ -0,0 +1,3 @@
+/* automatically generated by rust-bindgen 0.69.4 */
+
+pub const VERSION: u32 = 1;
@@ -0,0 +1,48 @@
+mod inner;
+use std::fmt;
+use std::collections::{HashMap, HashSet};
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+enum Shape {
+    Circle,
+    Square,
+}
+
+union Bits {
+    i: u32,
+    f: f32,
+}
+
+trait Area {
+    fn area(&self) -> f64;
+}
+
+impl Point {}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.x)
+    }
+}
+
+impl Area for Shape {
+    fn area(&self) -> f64 {
+        1.0
+    }
+}
+
+type Map = HashMap<u32, HashSet<u32>>;
+
+macro_rules! square {
+    ($x:expr) => {
+        $x * $x
+    };
+}
+
+mod nested {
+    pub struct Empty;
+}
@@ -0,0 +1,2 @@
+/* Franois wrote this. */
+int caf = 1;
@@ -0,0 +1,10 @@
+def classify(x):
+    match x:
+        case 1:
+            return 2
+        case [_, y]:
+            return y
+        case _ if x:
+            return 1
+        case _:
+            return 0
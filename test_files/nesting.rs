@@ -0,0 +1,21 @@
+fn blocks(v: &[u8]) -> u8 {
+    {
+        let p = v.as_ptr();
+        unsafe {
+            match *p {
+                0 => 1,
+                _ => 2,
+            }
+        }
+    }
+}
+
+fn chain(a: u8) -> u8 {
+    if a == 0 {
+        0
+    } else if a == 1 {
+        1
+    } else {
+        2
+    }
+}
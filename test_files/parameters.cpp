@@ -0,0 +1,7 @@
+int *first(int a, int b) { return nullptr; }
+
+int &second(int a) { return a; }
+
+int none(void) { return 0; }
+
+void many(int a, int b, int c, int d, int e, int f) {}
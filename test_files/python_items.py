@@ -0,0 +1,38 @@
+"""Shapes and their areas."""
+import math
+from dataclasses import dataclass
+
+SCALE = 2
+
+
+@dataclass
+class Shape:
+    name: str = "shape"
+
+    def area(self):
+        return 0
+
+
+class Circle(Shape, metaclass=type):
+    def __init__(self, radius):
+        self.radius = radius
+
+    @property
+    def area(self):
+        return math.pi * self.radius**2
+
+
+def total(shapes, scale=SCALE):
+    result = 0
+    for shape in shapes:
+        result += shape.area
+    squares = [s.area**2 for s in shapes if s.area > 0]
+    names = {s.name: s for s in shapes}
+    while result > 100:
+        result = result / scale
+    return sorted(squares, key=lambda s: -s) and result
+
+
+if __name__ == "__main__":
+    shapes = [Circle(1)]
+    print(total(shapes))
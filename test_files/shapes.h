@@ -0,0 +1,11 @@
+#pragma once
+
+namespace shapes {
+class Circle {
+public:
+    double area() const;
+
+private:
+    double r;
+};
+} // namespace shapes
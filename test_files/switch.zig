@@ -0,0 +1,7 @@
+fn classify(x: u8) u8 {
+    return switch (x) {
+        1 => 2,
+        2, 3 => 4,
+        else => 0,
+    };
+}
@@ -0,0 +1,9 @@
+int add(int a, int b) { return a + b; }
+
+TEST(Add, Small) {
+    EXPECT_EQ(add(1, 2), 3);
+}
+
+TEST_F(AddFixture, Large) {
+    EXPECT_EQ(add(1000, 2000), 3000);
+}
@@ -0,0 +1,24 @@
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+#[test]
+#[ignore]
+#[cfg(unix)]
+fn adds() {
+    assert_eq!(add(1, 2), 3);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    #[should_panic]
+    fn overflows() {
+        add(u32::MAX, 1);
+    }
+
+    fn helper() {}
+}
@@ -0,0 +1,7 @@
+fn add(a: u32, b: u32) u32 {
+    return a + b;
+}
+
+test "add" {
+    try expect(add(1, 2) == 3);
+}
@@ -0,0 +1,12 @@
+pub fn setup() -> Vec<u32> {
+    vec![1, 2, 3]
+}
+
+pub fn teardown(v: Vec<u32>) {
+    drop(v);
+}
+
+#[test]
+fn round_trip() {
+    teardown(setup());
+}
@@ -0,0 +1,4 @@
+int inflate(int n)
+{
+    return n;
+}
@@ -0,0 +1,9 @@
+# TODO(anna): Split this file.
+import sys
+
+TODOS = "TODO: not a comment"
+
+
+def main():  # FIXME handle errors
+    # XXX
+    return sys.argv  # Not a TODOS marker.
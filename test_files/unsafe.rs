@@ -0,0 +1,29 @@
+extern "C" {
+    fn abs(x: i32) -> i32;
+}
+
+unsafe trait Zeroable {
+    unsafe fn zeroed() -> Self;
+}
+
+unsafe impl Zeroable for u32 {
+    unsafe fn zeroed() -> Self {
+        0
+    }
+}
+
+impl Clone for Wrapper {
+    fn clone(&self) -> Self {
+        Wrapper(self.0)
+    }
+}
+
+fn read(p: *const u32, r: &u32) -> u32 {
+    let a = *r;
+    let b = unsafe { *p };
+    let c = unsafe {
+        let x = abs(-1) as u32;
+        *p + x
+    };
+    a + b + c
+}
@@ -0,0 +1,36 @@
+const std = @import("std");
+const c = @cImport({
+    @cInclude("stdio.h");
+});
+
+const Error = error{ OutOfMemory, Invalid };
+
+const Point = struct {
+    x: i32,
+    y: i32,
+};
+
+const Color = enum { red, green };
+
+const Value = union(enum) {
+    int: i64,
+    float: f64,
+};
+
+fn max(comptime T: type, a: T, b: T) T {
+    return if (a > b) a else b;
+}
+
+fn run(allocator: std.mem.Allocator) !void {
+    const buf = try allocator.alloc(u8, 10);
+    defer allocator.free(buf);
+    errdefer std.debug.print("failed\n", .{});
+    try check(buf);
+    comptime {
+        std.debug.assert(max(u8, 1, 2) == 2);
+    }
+}
+
+test "max" {
+    try std.testing.expect(max(i32, 1, 2) == 2);
+}